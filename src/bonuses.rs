@@ -0,0 +1,374 @@
+use crate::config::ConfigError;
+use crate::lp::{LinearModel, Sense, Terms};
+use crate::names::{BandId, BandNames, BandRef};
+use crate::{Econochella, Money};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Extra value earned by a combination of bookings, beyond each band's own value,
+/// shared by every clone of the festival across threads
+pub trait Bonus: Send + Sync {
+    /// a short human readable description of the bonus
+    fn describe(&self) -> String;
+    /// the bonus earned by the current schedules in dollars
+    fn value(&self, festival: &Econochella) -> Money;
+    /// true if the value is a sum over venues of an amount depending only on that venue's
+    /// schedule, false if it depends only on which bands are booked, as by default
+    fn per_venue(&self) -> bool {
+        false
+    }
+    /// the most the bonus can earn in any schedule of the festival's bands;
+    /// by default `Money::MAX`, which stops the exact search from pruning by its bound
+    fn upper_bound(&self, _festival: &Econochella) -> Money {
+        Money::MAX
+    }
+    /// add the bonus to a mixed integer program's objective, with any rows it needs,
+    /// returning false if it cannot be expressed that way, as by default
+    fn linearize(&self, _model: &mut LinearModel) -> bool {
+        false
+    }
+}
+
+/// a new binary that is at most each sum of terms, and adds `amount` to the objective,
+/// so it is 1 exactly when every sum is positive if `amount` is positive
+fn add_conjunction(model: &mut LinearModel, prefix: &str, sums: Vec<Terms>, amount: Money) {
+    let earned = model.add_binary(prefix);
+    for sum in sums {
+        let mut terms = vec![(1, earned.clone())];
+        terms.extend(sum.into_iter().map(|(coefficient, x)| (-coefficient, x)));
+        model.add_row(terms, Sense::AtMost, 0);
+    }
+    model.add_objective(amount, earned);
+}
+
+/// how many sets of the band the festival could book
+fn set_count(festival: &Econochella, id: BandId) -> Money {
+    festival
+        .knapsack
+        .iter()
+        .filter(|(band, _)| band.id == id)
+        .count() as Money
+}
+
+/// A bonus as written in the festival file, naming bands and genres
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BonusConfig {
+    /// `amount` for every set played directly before or after one of the band's sets
+    Adjacency { band: String, amount: Money },
+    /// `amount` if both bands are booked
+    CoBooking {
+        band: String,
+        other: String,
+        amount: Money,
+    },
+    /// `amount` for every set of the band played between two sets of `genre`
+    GenreContrast {
+        band: String,
+        genre: String,
+        amount: Money,
+    },
+}
+
+impl BonusConfig {
+    /// check the names against the roster and genres, and build the bonus
+    pub fn build(
+        &self,
+        bands: &BandNames,
+        genres: &HashMap<String, Vec<String>>,
+    ) -> Result<Box<dyn Bonus>, ConfigError> {
+        let band = |name: &String| -> Result<BandRef, ConfigError> {
+            bands
+                .resolve(name)
+                .map_err(|err| ConfigError::Invalid(format!("bonus refers to {}", err)))
+        };
+        let bonus: Box<dyn Bonus> = match self {
+            BonusConfig::Adjacency { band: name, amount } => Box::new(AdjacencyBonus {
+                band: band(name)?,
+                amount: *amount,
+            }),
+            BonusConfig::CoBooking {
+                band: name,
+                other,
+                amount,
+            } => Box::new(CoBookingBonus {
+                band: band(name)?,
+                other: band(other)?,
+                amount: *amount,
+            }),
+            BonusConfig::GenreContrast {
+                band: name,
+                genre,
+                amount,
+            } => Box::new(GenreContrastBonus {
+                band: band(name)?,
+                genre: genre.clone(),
+                members: genres
+                    .get(genre)
+                    .ok_or_else(|| {
+                        ConfigError::Invalid(format!("bonus refers to unknown genre {}", genre))
+                    })?
+                    .iter()
+                    .map(band)
+                    .collect::<Result<_, _>>()?,
+                amount: *amount,
+            }),
+        };
+        Ok(bonus)
+    }
+}
+
+/// Each set directly before or after the band's sets is worth `amount` more
+pub struct AdjacencyBonus {
+    pub band: BandRef,
+    pub amount: Money,
+}
+
+impl Bonus for AdjacencyBonus {
+    fn describe(&self) -> String {
+        format!("playing next to {}", self.band)
+    }
+    fn value(&self, festival: &Econochella) -> Money {
+        let neighbours: usize = festival
+            .venues
+            .iter()
+            .map(|venue| {
+                let ids = venue.band_ids();
+                (0..ids.len())
+                    .filter(|&position| {
+                        ids[position] != self.band.id
+                            && ((position > 0 && ids[position - 1] == self.band.id)
+                                || ids.get(position + 1) == Some(&self.band.id))
+                    })
+                    .count()
+            })
+            .sum();
+        neighbours as Money * self.amount
+    }
+    fn per_venue(&self) -> bool {
+        true
+    }
+    fn upper_bound(&self, festival: &Econochella) -> Money {
+        // each set has at most two neighbours
+        2 * set_count(festival, self.band.id) * self.amount.max(0)
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        // the objective only pushes the binaries up, so a penalty could be dodged
+        if self.amount < 0 {
+            return false;
+        }
+        let entries = model.entries(self.band.id);
+        let others = model.entries_except(&[self.band.id]);
+        for venue in 0..model.venue_count() {
+            for position in 0..model.positions(venue) {
+                // another band's set next to at least one of the band's
+                let mut beside = model.at(&entries, venue, position + 1);
+                if position > 0 {
+                    beside.extend(model.at(&entries, venue, position - 1));
+                }
+                let sums = vec![model.at(&others, venue, position), beside];
+                add_conjunction(model, "adjacent", sums, self.amount);
+            }
+        }
+        true
+    }
+}
+
+/// Booking both bands is worth `amount` more
+pub struct CoBookingBonus {
+    pub band: BandRef,
+    pub other: BandRef,
+    pub amount: Money,
+}
+
+impl Bonus for CoBookingBonus {
+    fn describe(&self) -> String {
+        format!("{} with {}", self.band, self.other)
+    }
+    fn value(&self, festival: &Econochella) -> Money {
+        if !festival.locations_of(self.band.id).is_empty()
+            && !festival.locations_of(self.other.id).is_empty()
+        {
+            self.amount
+        } else {
+            0
+        }
+    }
+    fn upper_bound(&self, _festival: &Econochella) -> Money {
+        self.amount.max(0)
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        if self.amount < 0 {
+            return false;
+        }
+        let sums = [&self.band, &self.other]
+            .iter()
+            .map(|band| {
+                model
+                    .entries(band.id)
+                    .into_iter()
+                    .flat_map(|entry| model.booked(entry))
+                    .collect()
+            })
+            .collect();
+        add_conjunction(model, "co_booked", sums, self.amount);
+        true
+    }
+}
+
+/// Each set of the band between two sets of a genre is worth `amount` more
+pub struct GenreContrastBonus {
+    pub band: BandRef,
+    pub genre: String,
+    /// the bands of the genre
+    pub members: Vec<BandRef>,
+    pub amount: Money,
+}
+
+impl Bonus for GenreContrastBonus {
+    fn describe(&self) -> String {
+        format!("{} between {} sets", self.band, self.genre)
+    }
+    fn value(&self, festival: &Econochella) -> Money {
+        let in_genre = |id: BandId| self.members.iter().any(|member| member.id == id);
+        let contrasts: usize = festival
+            .venues
+            .iter()
+            .map(|venue| {
+                venue
+                    .band_ids()
+                    .windows(3)
+                    .filter(|ids| ids[1] == self.band.id && in_genre(ids[0]) && in_genre(ids[2]))
+                    .count()
+            })
+            .sum();
+        contrasts as Money * self.amount
+    }
+    fn per_venue(&self) -> bool {
+        true
+    }
+    fn upper_bound(&self, festival: &Econochella) -> Money {
+        set_count(festival, self.band.id) * self.amount.max(0)
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        if self.amount < 0 {
+            return false;
+        }
+        let entries = model.entries(self.band.id);
+        let members: Vec<usize> = self
+            .members
+            .iter()
+            .flat_map(|member| model.entries(member.id))
+            .collect();
+        for venue in 0..model.venue_count() {
+            for position in 1..model.positions(venue).saturating_sub(1) {
+                let sums = vec![
+                    model.at(&entries, venue, position),
+                    model.at(&members, venue, position - 1),
+                    model.at(&members, venue, position + 1),
+                ];
+                add_conjunction(model, "contrast", sums, self.amount);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ClockTime;
+    use crate::test_support::band;
+    use crate::Venue;
+
+    /// two long stages, and bands a (two sets, entries 0 and 1), b, c and d (entries 2 to 4),
+    /// with each stage playing the entries listed for it in order
+    fn festival(stages: [&[usize]; 2]) -> Econochella {
+        let stage = |name: &str| Venue::new(name.to_string(), ClockTime::NOON, 0, 600);
+        let mut festival = Econochella::new(
+            1000,
+            vec![stage("tent"), stage("stadium")],
+            ["a", "a", "b", "c", "d"]
+                .iter()
+                .map(|name| band(name, 100, 10))
+                .collect(),
+        );
+        for (venue, entries) in stages.iter().enumerate() {
+            for &entry in *entries {
+                festival.book(entry, venue);
+            }
+        }
+        festival
+    }
+
+    fn named(festival: &Econochella, name: &str) -> BandRef {
+        festival.names().resolve(name).unwrap()
+    }
+
+    #[test]
+    fn counts_each_neighbour_of_a_band_once() {
+        let value = |stages| {
+            let festival = festival(stages);
+            let bonus = AdjacencyBonus {
+                band: named(&festival, "a"),
+                amount: 10,
+            };
+            bonus.value(&festival)
+        };
+        assert_eq!(value([&[2, 0, 3], &[]]), 20);
+        // b is between both of a's sets, but is one neighbour
+        assert_eq!(value([&[0, 2, 1], &[]]), 10);
+        // a's own sets are not neighbours
+        assert_eq!(value([&[0, 1], &[2]]), 0);
+        assert_eq!(value([&[0, 2], &[3, 1, 4]]), 30);
+        let festival = festival([&[], &[]]);
+        let bonus = AdjacencyBonus {
+            band: named(&festival, "a"),
+            amount: 10,
+        };
+        assert_eq!(bonus.upper_bound(&festival), 40);
+    }
+
+    #[test]
+    fn counts_sets_between_two_of_a_genre() {
+        let value = |stages| {
+            let festival = festival(stages);
+            let bonus = GenreContrastBonus {
+                band: named(&festival, "a"),
+                genre: "folk".to_string(),
+                members: vec![
+                    named(&festival, "b"),
+                    named(&festival, "c"),
+                    named(&festival, "d"),
+                ],
+                amount: 5,
+            };
+            bonus.value(&festival)
+        };
+        assert_eq!(value([&[2, 0, 3], &[]]), 5);
+        // both of a's sets, sharing the c between them
+        assert_eq!(value([&[2, 0, 3, 1, 4], &[]]), 10);
+        // a's other set is not of the genre
+        assert_eq!(value([&[2, 0, 1], &[]]), 0);
+        // a genre set on another stage does not count
+        assert_eq!(value([&[2, 0], &[3]]), 0);
+        assert_eq!(value([&[0, 2, 1], &[]]), 0);
+    }
+
+    #[test]
+    fn pays_once_for_booking_both_bands() {
+        let value = |stages| {
+            let festival = festival(stages);
+            let bonus = CoBookingBonus {
+                band: named(&festival, "a"),
+                other: named(&festival, "d"),
+                amount: 7,
+            };
+            bonus.value(&festival)
+        };
+        assert_eq!(value([&[], &[]]), 0);
+        assert_eq!(value([&[0, 2], &[]]), 0);
+        assert_eq!(value([&[0], &[4]]), 7);
+        assert_eq!(value([&[0, 4], &[1]]), 7);
+    }
+}
@@ -0,0 +1,96 @@
+use core::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+
+/// A time of day on the festival clock, which runs from noon until noon the next day,
+/// so that sets can run past midnight and still sort after the evening's
+///
+/// Written as a 24-hour time such as "21:00"; times before noon are the next morning
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClockTime(u32);
+
+impl ClockTime {
+    /// the start of the festival day
+    pub const NOON: ClockTime = ClockTime(0);
+    /// the length of a festival day, from noon to noon
+    pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+    pub fn from_minutes_since_noon(minutes: u32) -> ClockTime {
+        ClockTime(minutes)
+    }
+    pub fn minutes_since_noon(self) -> u32 {
+        self.0
+    }
+    /// whether this is a time before noon, which the festival clock places at the end of the day
+    pub fn is_morning(self) -> bool {
+        self.0 >= 12 * 60
+    }
+    /// the minutes from `earlier` until this time, or none if `earlier` is later
+    pub fn minutes_since(self, earlier: ClockTime) -> Option<u32> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl Add<u32> for ClockTime {
+    type Output = ClockTime;
+    /// the time this many minutes later
+    fn add(self, minutes: u32) -> ClockTime {
+        ClockTime(self.0 + minutes)
+    }
+}
+
+/// A clock time that is not a 24-hour time such as "21:00"
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseClockTimeError(String);
+
+impl fmt::Display for ParseClockTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid clock time {:?}", self.0)
+    }
+}
+
+impl FromStr for ClockTime {
+    type Err = ParseClockTimeError;
+    fn from_str(time: &str) -> Result<ClockTime, ParseClockTimeError> {
+        let invalid = || ParseClockTimeError(time.to_string());
+        let (hours, minutes) = time.trim().split_once(':').ok_or_else(invalid)?;
+        let hours: u32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+        // shift midnight to noon, wrapping the morning to the end of the day
+        Ok(ClockTime(((hours + 12) % 24) * 60 + minutes))
+    }
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = 12 * 60 + self.0;
+        write!(f, "{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> ClockTime {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn runs_from_noon_past_midnight() {
+        assert_eq!(time("12:00"), ClockTime::NOON);
+        assert_eq!(time("21:30").minutes_since_noon(), 570);
+        assert_eq!(time("00:00").minutes_since_noon(), 720);
+        assert!(time("01:30") > time("23:00"));
+        assert_eq!(time("01:30").minutes_since(time("23:00")), Some(150));
+        assert_eq!(time("23:00").minutes_since(time("01:30")), None);
+        assert_eq!((time("23:15") + 90).to_string(), "00:45");
+        assert!(time("11:00").is_morning());
+        assert!(!time("23:59").is_morning());
+        assert!("24:00".parse::<ClockTime>().is_err());
+        assert!("9pm".parse::<ClockTime>().is_err());
+    }
+}
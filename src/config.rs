@@ -0,0 +1,228 @@
+use crate::bonuses::BonusConfig;
+use crate::clock::{ClockTime, ParseClockTimeError};
+use crate::constraints::ConstraintConfig;
+use crate::revenue::RevenueCurveConfig;
+use crate::roster::RosterError;
+use crate::rules::{parse_rules, Rule, RuleError};
+use crate::Money;
+use core::fmt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A festival description: the venues, their hours, the budget and the roster
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FestivalConfig {
+    /// total budget in dollars
+    pub budget: Money,
+    /// path to the band roster, relative to the festival file
+    pub roster: PathBuf,
+    /// the days of a multi-day festival in order, such as ["Friday", "Saturday"];
+    /// none for a single day
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// the venues that bands can be booked into
+    pub venues: Vec<VenueConfig>,
+    /// booking rules that every schedule must satisfy
+    #[serde(default)]
+    pub constraints: Vec<ConstraintConfig>,
+    /// bands grouped by genre, for genre bonuses
+    #[serde(default)]
+    pub genres: HashMap<String, Vec<String>>,
+    /// extra value for combinations of bookings
+    #[serde(default)]
+    pub bonuses: Vec<BonusConfig>,
+    /// revenue multipliers by start time for time-sensitive bands
+    #[serde(default)]
+    pub revenue_curves: Vec<RevenueCurveConfig>,
+    /// optional rules file, relative to the festival file, adding to `constraints`
+    pub rules: Option<PathBuf>,
+    /// the rules read from the `rules` file, with their positions in it
+    #[serde(skip)]
+    pub rule_file: Vec<Rule>,
+}
+
+/// A venue's name, hours and standard break
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueConfig {
+    pub name: String,
+    /// opening clock time, e.g. "17:00"
+    pub opens: String,
+    /// curfew clock time, e.g. "22:00" or "01:00" past midnight, by which the last set ends
+    #[serde(alias = "curfew")]
+    pub closes: String,
+    /// standard break between sets in minutes
+    #[serde(rename = "break")]
+    pub break_time: u32,
+    /// hours on particular days of a multi-day festival, overriding `opens` and `closes`
+    #[serde(default)]
+    pub hours: Vec<DayHoursConfig>,
+}
+
+/// A venue's hours on one day of a multi-day festival
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DayHoursConfig {
+    pub day: String,
+    pub opens: String,
+    #[serde(alias = "curfew")]
+    pub closes: String,
+}
+
+impl VenueConfig {
+    pub fn opening_time(&self) -> Result<ClockTime, ConfigError> {
+        Ok(self.opens.parse()?)
+    }
+    /// minutes between opening and the curfew
+    pub fn total_time(&self) -> Result<u32, ConfigError> {
+        open_minutes(&self.name, self.opening_time()?, self.closes.parse()?)
+    }
+    /// the opening time and minutes open on a day of the festival
+    pub fn hours_on(&self, day: &str, days: &[String]) -> Result<(ClockTime, u32), ConfigError> {
+        for hours in &self.hours {
+            if !days.contains(&hours.day) {
+                return Err(ConfigError::Invalid(format!(
+                    "the {} has hours on unknown day {}",
+                    self.name, hours.day
+                )));
+            }
+        }
+        match self.hours.iter().find(|hours| hours.day == day) {
+            Some(hours) => {
+                let opens = hours.opens.parse()?;
+                Ok((
+                    opens,
+                    open_minutes(&self.name, opens, hours.closes.parse()?)?,
+                ))
+            }
+            None => Ok((self.opening_time()?, self.total_time()?)),
+        }
+    }
+}
+
+/// minutes between a venue's opening and curfew
+fn open_minutes(venue: &str, opens: ClockTime, closes: ClockTime) -> Result<u32, ConfigError> {
+    closes
+        .minutes_since(opens)
+        .filter(|minutes| *minutes > 0)
+        .ok_or_else(|| {
+            let mut message = format!(
+                "the {} closes at {}, before it opens at {}",
+                venue, closes, opens
+            );
+            if opens.is_morning() {
+                message.push_str(&format!(
+                    "; the festival day runs from noon to noon, so {} is the next morning",
+                    opens
+                ));
+            }
+            ConfigError::Invalid(message)
+        })
+}
+
+/// An error encountered while reading a festival file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// the file could not be read
+    Io(io::Error),
+    /// the file is not valid TOML or does not match the expected layout
+    Parse(toml::de::Error),
+    /// the roster could not be loaded
+    Roster(RosterError),
+    /// the rules file has a malformed rule
+    Rules(PathBuf, RuleError),
+    /// the file parsed but describes an impossible festival
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "unable to read festival file: {}", err),
+            ConfigError::Parse(err) => write!(f, "invalid festival file: {}", err),
+            ConfigError::Roster(err) => write!(f, "{}", err),
+            ConfigError::Rules(path, err) => write!(f, "{}:{}", path.display(), err),
+            ConfigError::Invalid(message) => write!(f, "invalid festival file: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> ConfigError {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<ParseClockTimeError> for ConfigError {
+    fn from(err: ParseClockTimeError) -> ConfigError {
+        ConfigError::Invalid(err.to_string())
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> ConfigError {
+        ConfigError::Parse(err)
+    }
+}
+
+impl FestivalConfig {
+    /// Load a festival file, resolving the roster path relative to it
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<FestivalConfig, ConfigError> {
+        let path = path.as_ref();
+        let mut config: FestivalConfig = toml::from_str(&fs::read_to_string(path)?)?;
+        if let Some(dir) = path.parent() {
+            config.roster = dir.join(&config.roster);
+            config.rules = config.rules.map(|rules| dir.join(rules));
+        }
+        if let Some(rules) = &config.rules {
+            let source = fs::read_to_string(rules)?;
+            config.rule_file =
+                parse_rules(&source).map_err(|err| ConfigError::Rules(rules.clone(), err))?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn venue(opens: &str, closes: &str) -> VenueConfig {
+        VenueConfig {
+            name: "amphitheater".to_string(),
+            opens: opens.to_string(),
+            closes: closes.to_string(),
+            break_time: 0,
+            hours: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn counts_minutes_open_past_midnight() {
+        assert_eq!(venue("17:00", "22:00").total_time().unwrap(), 5 * 60);
+        assert_eq!(venue("17:00", "01:30").total_time().unwrap(), 8 * 60 + 30);
+        assert_eq!(
+            venue("23:00", "22:00")
+                .total_time()
+                .unwrap_err()
+                .to_string(),
+            "invalid festival file: the amphitheater closes at 22:00, before it opens at 23:00"
+        );
+    }
+
+    #[test]
+    fn explains_that_mornings_end_the_day() {
+        assert_eq!(
+            venue("11:00", "22:00")
+                .total_time()
+                .unwrap_err()
+                .to_string(),
+            "invalid festival file: the amphitheater closes at 22:00, before it opens at 11:00; \
+             the festival day runs from noon to noon, so 11:00 is the next morning"
+        );
+    }
+}
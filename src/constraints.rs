@@ -0,0 +1,987 @@
+use crate::clock::ClockTime;
+use crate::config::ConfigError;
+use crate::lp::{LinearModel, Sense, Terms};
+use crate::names::{BandNames, BandRef};
+use crate::violation::Violation;
+use crate::{Econochella, Money, TimeSlot, Venue};
+use serde::Deserialize;
+
+/// A booking rule that a valid festival must satisfy, shared by every clone of the festival
+/// across threads
+pub trait Constraint: Send + Sync {
+    /// a short human readable description of the rule
+    fn describe(&self) -> String;
+    /// every way in which the current schedules break the rule, counting only the schedules
+    /// of `day` if given, as if no other day were booked
+    /// A violation naming a venue must depend only on that venue's schedule, and one naming
+    /// no venue only on where each set is booked, and on when the sets start of the bands it
+    /// names that are booked for more than one
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation>;
+    /// add the rule to a mixed integer program as linear rows,
+    /// returning false if it cannot be expressed that way, as by default
+    fn linearize(&self, _model: &mut LinearModel) -> bool {
+        false
+    }
+}
+
+/// every set of these entries at any of a venue's positions
+fn at_venue(model: &LinearModel, entries: &[usize], venue: usize) -> Terms {
+    (0..model.positions(venue))
+        .flat_map(|position| model.at(entries, venue, position))
+        .collect()
+}
+
+/// A constraint as written in the festival file, naming bands and venues
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ConstraintConfig {
+    /// the band's sets must start after a clock time
+    StartsAfter { band: String, time: String },
+    /// the band's sets must start no later than a clock time
+    StartsBefore { band: String, time: String },
+    /// the band may not play in a venue
+    NotIn { band: String, venue: String },
+    /// the band may not share a stage with any of the others
+    NeverSameStageAs { band: String, others: Vec<String> },
+    /// if the band is booked, the other band must be booked too
+    Requires { band: String, other: String },
+    /// the band must play the last set of its stage
+    ClosesStage { band: String },
+    /// none of the others may play directly before or after the band
+    NotAdjacentTo { band: String, others: Vec<String> },
+    /// the band's sets on the same stage must follow each other
+    BackToBack { band: String },
+    /// all of the band's sets must be on one stage
+    SameStage { band: String },
+    /// each of the band's sets must end at least this many minutes before the next starts
+    MinGap { band: String, minutes: u32 },
+    /// no two of the band's sets may be on the same day
+    DifferentDays { band: String },
+    /// the band may only play on these days
+    AvailableOn { band: String, days: Vec<String> },
+    /// another rule, applying only to the schedules of one day
+    OnDay {
+        day: String,
+        rule: Box<ConstraintConfig>,
+    },
+}
+
+impl ConstraintConfig {
+    /// check the names against the roster, venues and days, and build the constraint
+    pub fn build(
+        &self,
+        bands: &BandNames,
+        venues: &[String],
+        days: &[String],
+    ) -> Result<Box<dyn Constraint>, ConfigError> {
+        let day = |name: &String| -> Result<String, ConfigError> {
+            if days.contains(name) {
+                Ok(name.clone())
+            } else {
+                Err(ConfigError::Invalid(format!(
+                    "constraint refers to unknown day {}",
+                    name
+                )))
+            }
+        };
+        let band = |name: &String| -> Result<BandRef, ConfigError> {
+            bands
+                .resolve(name)
+                .map_err(|err| ConfigError::Invalid(format!("constraint refers to {}", err)))
+        };
+        let constraint: Box<dyn Constraint> = match self {
+            ConstraintConfig::StartsAfter { band: name, time } => Box::new(TimeWindow {
+                band: band(name)?,
+                after: Some(time.parse()?),
+                before: None,
+            }),
+            ConstraintConfig::StartsBefore { band: name, time } => Box::new(TimeWindow {
+                band: band(name)?,
+                after: None,
+                before: Some(time.parse()?),
+            }),
+            ConstraintConfig::NotIn { band: name, venue } => {
+                if !venues.contains(venue) {
+                    return Err(ConfigError::Invalid(format!(
+                        "constraint refers to unknown venue {}",
+                        venue
+                    )));
+                }
+                Box::new(ForbiddenVenue {
+                    band: band(name)?,
+                    venue: venue.clone(),
+                })
+            }
+            ConstraintConfig::NeverSameStageAs { band: name, others } => Box::new(NotSameStageAs {
+                band: band(name)?,
+                others: others.iter().map(band).collect::<Result<_, _>>()?,
+            }),
+            ConstraintConfig::Requires { band: name, other } => Box::new(RequiresCoBooking {
+                band: band(name)?,
+                other: band(other)?,
+            }),
+            ConstraintConfig::ClosesStage { band: name } => {
+                Box::new(ClosesStage { band: band(name)? })
+            }
+            ConstraintConfig::NotAdjacentTo { band: name, others } => Box::new(NotAdjacentTo {
+                band: band(name)?,
+                others: others.iter().map(band).collect::<Result<_, _>>()?,
+            }),
+            ConstraintConfig::BackToBack { band: name } => {
+                Box::new(BackToBack { band: band(name)? })
+            }
+            ConstraintConfig::SameStage { band: name } => Box::new(SameStage { band: band(name)? }),
+            ConstraintConfig::MinGap {
+                band: name,
+                minutes,
+            } => Box::new(MinGap {
+                band: band(name)?,
+                minutes: *minutes,
+            }),
+            ConstraintConfig::DifferentDays { band: name } => {
+                Box::new(DifferentDays { band: band(name)? })
+            }
+            ConstraintConfig::AvailableOn { band: name, days } => Box::new(AvailableOn {
+                band: band(name)?,
+                days: days.iter().map(day).collect::<Result<_, _>>()?,
+            }),
+            ConstraintConfig::OnDay { day: name, rule } => Box::new(OnDay {
+                day: day(name)?,
+                rule: rule.build(bands, venues, days)?,
+            }),
+        };
+        Ok(constraint)
+    }
+}
+
+/// the bands' names, separated by commas
+fn names(bands: &[BandRef]) -> String {
+    bands
+        .iter()
+        .map(|band| band.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// a violation of `constraint` by `bands` in `venue`
+fn violation(constraint: &dyn Constraint, bands: Vec<BandRef>, venue: &Venue) -> Violation {
+    Violation::Constraint {
+        rule: constraint.describe(),
+        bands,
+        venue: Some(venue.label()),
+    }
+}
+
+/// Every set of the band starts strictly after `after` and no later than `before`
+pub struct TimeWindow {
+    pub band: BandRef,
+    pub after: Option<ClockTime>,
+    pub before: Option<ClockTime>,
+}
+
+impl Constraint for TimeWindow {
+    fn describe(&self) -> String {
+        match (self.after, self.before) {
+            (Some(after), Some(before)) => {
+                format!("{} starts after {} and by {}", self.band, after, before)
+            }
+            (Some(after), None) => format!("{} starts after {}", self.band, after),
+            (None, Some(before)) => format!("{} starts by {}", self.band, before),
+            (None, None) => format!("{} starts at any time", self.band),
+        }
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .flat_map(|venue| {
+                venue
+                    .band_slots(self.band.id)
+                    .filter(move |(_, start_time)| {
+                        self.after.is_some_and(|after| *start_time <= after)
+                            || self.before.is_some_and(|before| *start_time > before)
+                    })
+                    .map(move |_| violation(self, vec![self.band.clone()], venue))
+            })
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        let m = model.big_m();
+        for entry in model.entries(self.band.id) {
+            let start = model.start(entry);
+            let booked = model.booked(entry);
+            // start > after, and start <= before, when booked
+            if let Some(after) = self.after {
+                let mut terms = vec![(1, start.clone())];
+                terms.extend(booked.iter().map(|(_, x)| (-m, x.clone())));
+                model.add_row(
+                    terms,
+                    Sense::AtLeast,
+                    after.minutes_since_noon() as Money + 1 - m,
+                );
+            }
+            if let Some(before) = self.before {
+                let mut terms = vec![(1, start.clone())];
+                terms.extend(booked.iter().map(|(_, x)| (m, x.clone())));
+                model.add_row(
+                    terms,
+                    Sense::AtMost,
+                    before.minutes_since_noon() as Money + m,
+                );
+            }
+        }
+        true
+    }
+}
+
+/// The band may not play in the named venue
+pub struct ForbiddenVenue {
+    pub band: BandRef,
+    pub venue: String,
+}
+
+impl Constraint for ForbiddenVenue {
+    fn describe(&self) -> String {
+        format!("{} does not play in the {}", self.band, self.venue)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .filter(|venue| venue.name == self.venue && venue.has_band(self.band.id))
+            .map(|venue| violation(self, vec![self.band.clone()], venue))
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        for venue in model.venue_ids(&self.venue) {
+            let sets = at_venue(model, &model.entries(self.band.id), venue);
+            model.add_row(sets, Sense::AtMost, 0);
+        }
+        true
+    }
+}
+
+/// The band may not play on a stage with any of the others
+pub struct NotSameStageAs {
+    pub band: BandRef,
+    pub others: Vec<BandRef>,
+}
+
+impl Constraint for NotSameStageAs {
+    fn describe(&self) -> String {
+        format!(
+            "{} never shares a stage with {}",
+            self.band,
+            names(&self.others)
+        )
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .filter(|venue| venue.has_band(self.band.id))
+            .filter_map(|venue| {
+                let mut bands: Vec<BandRef> = self
+                    .others
+                    .iter()
+                    .filter(|other| venue.has_band(other.id))
+                    .cloned()
+                    .collect();
+                if bands.is_empty() {
+                    return None;
+                }
+                bands.insert(0, self.band.clone());
+                Some(violation(self, bands, venue))
+            })
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        let others: Vec<usize> = self
+            .others
+            .iter()
+            .flat_map(|other| model.entries(other.id))
+            .collect();
+        for venue in 0..model.venue_count() {
+            for entry in model.entries(self.band.id) {
+                for &other in &others {
+                    let mut sets = at_venue(model, &[entry], venue);
+                    sets.extend(at_venue(model, &[other], venue));
+                    model.add_row(sets, Sense::AtMost, 1);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// If the band is booked, the other band must be booked in some venue
+pub struct RequiresCoBooking {
+    pub band: BandRef,
+    pub other: BandRef,
+}
+
+impl Constraint for RequiresCoBooking {
+    fn describe(&self) -> String {
+        format!("{} requires {}", self.band, self.other)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        if !festival.is_booked_on(self.band.id, day) || festival.is_booked_on(self.other.id, day) {
+            return Vec::new();
+        }
+        vec![Violation::Constraint {
+            rule: self.describe(),
+            bands: vec![self.band.clone(), self.other.clone()],
+            venue: None,
+        }]
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        let others: Terms = model
+            .entries(self.other.id)
+            .into_iter()
+            .flat_map(|other| model.booked(other))
+            .map(|(_, x)| (-1, x))
+            .collect();
+        for entry in model.entries(self.band.id) {
+            let mut terms = model.booked(entry);
+            terms.extend(others.iter().cloned());
+            model.add_row(terms, Sense::AtMost, 0);
+        }
+        true
+    }
+}
+
+/// The band must play the last set on every stage it plays on
+pub struct ClosesStage {
+    pub band: BandRef,
+}
+
+impl Constraint for ClosesStage {
+    fn describe(&self) -> String {
+        format!("{} closes the stage", self.band)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .filter(|venue| venue.has_band(self.band.id))
+            .filter_map(|venue| match venue.schedule.last() {
+                Some(TimeSlot::BandSlot(_, band)) if band.id == self.band.id => None,
+                Some(TimeSlot::BandSlot(_, band)) => Some(violation(
+                    self,
+                    vec![self.band.clone(), band.band_ref()],
+                    venue,
+                )),
+                _ => Some(violation(self, vec![self.band.clone()], venue)),
+            })
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        // no other band plays the set after any of the band's sets
+        let entries = model.entries(self.band.id);
+        let others = model.entries_except(&[self.band.id]);
+        for venue in 0..model.venue_count() {
+            for position in 1..model.positions(venue) {
+                let mut terms = model.at(&entries, venue, position - 1);
+                terms.extend(model.at(&others, venue, position));
+                model.add_row(terms, Sense::AtMost, 1);
+            }
+        }
+        true
+    }
+}
+
+/// None of the others may play the set directly before or after the band
+pub struct NotAdjacentTo {
+    pub band: BandRef,
+    pub others: Vec<BandRef>,
+}
+
+impl Constraint for NotAdjacentTo {
+    fn describe(&self) -> String {
+        format!(
+            "{} does not play next to {}",
+            self.band,
+            names(&self.others)
+        )
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .flat_map(|venue| {
+                let ids = venue.band_ids();
+                ids.windows(2)
+                    .filter_map(|pair| {
+                        let other = if pair[0] == self.band.id {
+                            pair[1]
+                        } else if pair[1] == self.band.id {
+                            pair[0]
+                        } else {
+                            return None;
+                        };
+                        let other = self.others.iter().find(|band| band.id == other)?;
+                        Some(violation(
+                            self,
+                            vec![self.band.clone(), other.clone()],
+                            venue,
+                        ))
+                    })
+                    .collect::<Vec<Violation>>()
+            })
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        let entries = model.entries(self.band.id);
+        let others: Vec<usize> = self
+            .others
+            .iter()
+            .flat_map(|other| model.entries(other.id))
+            .collect();
+        for venue in 0..model.venue_count() {
+            for position in 1..model.positions(venue) {
+                for (before, after) in [(&entries, &others), (&others, &entries)] {
+                    let mut terms = model.at(before, venue, position - 1);
+                    terms.extend(model.at(after, venue, position));
+                    model.add_row(terms, Sense::AtMost, 1);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// All of the band's sets on the same stage must be consecutive
+pub struct BackToBack {
+    pub band: BandRef,
+}
+
+impl Constraint for BackToBack {
+    fn describe(&self) -> String {
+        format!("{} plays its sets back to back", self.band)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .filter(|venue| {
+                let positions: Vec<usize> = venue
+                    .band_ids()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, id)| **id == self.band.id)
+                    .map(|(position, _)| position)
+                    .collect();
+                positions.windows(2).any(|pair| pair[1] - pair[0] != 1)
+            })
+            .map(|venue| violation(self, vec![self.band.clone()], venue))
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        // positions are filled in order, so a position between two of the band's sets
+        // that the band does not play is played by another band
+        let entries = model.entries(self.band.id);
+        for venue in 0..model.venue_count() {
+            let positions = model.positions(venue);
+            for first in 0..positions {
+                for last in first + 2..positions {
+                    for between in first + 1..last {
+                        let mut terms = model.at(&entries, venue, first);
+                        terms.extend(model.at(&entries, venue, last));
+                        terms.extend(
+                            model
+                                .at(&entries, venue, between)
+                                .into_iter()
+                                .map(|(_, x)| (-1, x)),
+                        );
+                        model.add_row(terms, Sense::AtMost, 1);
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// All of the band's sets are on one stage, on any day
+pub struct SameStage {
+    pub band: BandRef,
+}
+
+impl Constraint for SameStage {
+    fn describe(&self) -> String {
+        format!("{} plays all its sets on one stage", self.band)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        let mut stages: Vec<&str> = festival
+            .venues_on(day)
+            .filter(|venue| venue.has_band(self.band.id))
+            .map(|venue| venue.name())
+            .collect();
+        stages.sort_unstable();
+        stages.dedup();
+        if stages.len() <= 1 {
+            return Vec::new();
+        }
+        vec![Violation::Constraint {
+            rule: self.describe(),
+            bands: vec![self.band.clone()],
+            venue: None,
+        }]
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        // no two sets at venues with different names
+        let entries = model.entries(self.band.id);
+        for venue in 0..model.venue_count() {
+            for other in venue + 1..model.venue_count() {
+                if model.venue_name(venue) == model.venue_name(other) {
+                    continue;
+                }
+                for &entry in &entries {
+                    for &other_entry in &entries {
+                        if entry == other_entry {
+                            continue;
+                        }
+                        let mut sets = at_venue(model, &[entry], venue);
+                        sets.extend(at_venue(model, &[other_entry], other));
+                        model.add_row(sets, Sense::AtMost, 1);
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Between each of the band's sets and the next, on any stage or day, at least `minutes` pass
+/// from the end of the first to the start of the next
+pub struct MinGap {
+    pub band: BandRef,
+    pub minutes: u32,
+}
+
+impl Constraint for MinGap {
+    fn describe(&self) -> String {
+        format!(
+            "{} rests at least {} minutes between sets",
+            self.band, self.minutes
+        )
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        let sets: Vec<(u32, u32)> = festival
+            .booked_sets(day)
+            .into_iter()
+            .filter(|&(id, ..)| id == self.band.id)
+            .map(|(_, _, start, time)| (start, time))
+            .collect();
+        let rested = sets.windows(2).all(|pair| {
+            let (start, time) = pair[0];
+            pair[1]
+                .0
+                .checked_sub(start + time)
+                .is_some_and(|gap| gap >= self.minutes)
+        });
+        if rested {
+            return Vec::new();
+        }
+        vec![Violation::Constraint {
+            rule: self.describe(),
+            bands: vec![self.band.clone()],
+            venue: None,
+        }]
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        // each booked set starts at least the earlier set's time and the gap after it starts
+        let m = model.big_m();
+        let entries = model.entries(self.band.id);
+        for pair in entries.windows(2) {
+            let (earlier, entry) = (pair[0], pair[1]);
+            let time = model.set_time(earlier) as Money;
+            let mut gap = model.sequence_start(entry);
+            gap.extend(
+                model
+                    .sequence_start(earlier)
+                    .into_iter()
+                    .map(|(coefficient, variable)| (-coefficient, variable)),
+            );
+            gap.extend(model.booked(entry).into_iter().map(|(_, x)| (-m, x)));
+            model.add_row(gap, Sense::AtLeast, time + self.minutes as Money - m);
+        }
+        true
+    }
+}
+
+/// No two of the band's sets are on the same day
+pub struct DifferentDays {
+    pub band: BandRef,
+}
+
+impl Constraint for DifferentDays {
+    fn describe(&self) -> String {
+        format!("{} plays its sets on different days", self.band)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        let mut days: Vec<Option<&str>> = festival
+            .venues_on(day)
+            .flat_map(|venue| venue.band_slots(self.band.id).map(move |_| venue.day()))
+            .collect();
+        let sets = days.len();
+        days.sort();
+        days.dedup();
+        if days.len() == sets {
+            return Vec::new();
+        }
+        vec![Violation::Constraint {
+            rule: self.describe(),
+            bands: vec![self.band.clone()],
+            venue: None,
+        }]
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        let entries = model.entries(self.band.id);
+        let mut days: Vec<Option<String>> = (0..model.venue_count())
+            .map(|venue| model.day(venue))
+            .collect();
+        days.sort();
+        days.dedup();
+        for day in days {
+            let sets = (0..model.venue_count())
+                .filter(|&venue| model.day(venue) == day)
+                .flat_map(|venue| at_venue(model, &entries, venue))
+                .collect();
+            model.add_row(sets, Sense::AtMost, 1);
+        }
+        true
+    }
+}
+
+/// The band only plays on the listed days
+pub struct AvailableOn {
+    pub band: BandRef,
+    pub days: Vec<String>,
+}
+
+impl AvailableOn {
+    fn allows(&self, day: Option<&str>) -> bool {
+        day.is_some_and(|day| self.days.iter().any(|allowed| allowed == day))
+    }
+}
+
+impl Constraint for AvailableOn {
+    fn describe(&self) -> String {
+        format!("{} plays only on {}", self.band, self.days.join(", "))
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        festival
+            .venues_on(day)
+            .filter(|venue| !self.allows(venue.day()) && venue.has_band(self.band.id))
+            .map(|venue| violation(self, vec![self.band.clone()], venue))
+            .collect()
+    }
+    fn linearize(&self, model: &mut LinearModel) -> bool {
+        let entries = model.entries(self.band.id);
+        for venue in 0..model.venue_count() {
+            if !self.allows(model.day(venue).as_deref()) {
+                let sets = at_venue(model, &entries, venue);
+                model.add_row(sets, Sense::AtMost, 0);
+            }
+        }
+        true
+    }
+}
+
+/// Another rule, checked against the schedules of one day as if no other day were booked
+pub struct OnDay {
+    pub day: String,
+    pub rule: Box<dyn Constraint>,
+}
+
+impl Constraint for OnDay {
+    fn describe(&self) -> String {
+        format!("{} on {}", self.rule.describe(), self.day)
+    }
+    fn violations(&self, festival: &Econochella, day: Option<&str>) -> Vec<Violation> {
+        // a day within another day is only that day
+        if day.is_some_and(|day| day != self.day) {
+            return Vec::new();
+        }
+        self.rule
+            .violations(festival, Some(&self.day))
+            .into_iter()
+            .map(|violation| match violation {
+                Violation::Constraint { bands, venue, .. } => Violation::Constraint {
+                    rule: self.describe(),
+                    bands,
+                    venue,
+                },
+                other => other,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::band;
+
+    /// a tent and a stadium open ten hours from noon, on each day if there are any, and bands
+    /// a (two sets, entries 0 and 1), b, c and d (entries 2 to 4), with each venue, by day
+    /// then tent before stadium, playing the entries listed for it in order
+    fn festival(days: &[&str], venues: &[&[usize]]) -> Econochella {
+        let stage = |name: &str, day: Option<&str>| {
+            let mut venue = Venue::new(name.to_string(), ClockTime::NOON, 0, 600);
+            venue.day = day.map(str::to_string);
+            venue
+        };
+        let stages = if days.is_empty() {
+            vec![stage("tent", None), stage("stadium", None)]
+        } else {
+            days.iter()
+                .flat_map(|&day| [stage("tent", Some(day)), stage("stadium", Some(day))])
+                .collect()
+        };
+        let mut festival = Econochella::new(
+            1000,
+            stages,
+            ["a", "a", "b", "c", "d"]
+                .iter()
+                .map(|name| band(name, 100, 10))
+                .collect(),
+        );
+        festival.days = days.iter().map(|day| day.to_string()).collect();
+        for (venue, entries) in venues.iter().enumerate() {
+            for &entry in *entries {
+                festival.book(entry, venue);
+            }
+        }
+        festival
+    }
+
+    fn named(festival: &Econochella, name: &str) -> BandRef {
+        festival.names().resolve(name).unwrap()
+    }
+
+    /// the venue named by each of the rule's violations
+    fn broken(rule: &dyn Constraint, festival: &Econochella) -> Vec<Option<String>> {
+        rule.violations(festival, None)
+            .iter()
+            .map(|violation| violation.venue().map(str::to_string))
+            .collect()
+    }
+
+    /// the bands of the rule's only violation
+    fn culprits(rule: &dyn Constraint, festival: &Econochella) -> Vec<String> {
+        match &rule.violations(festival, None)[..] {
+            [violation] => violation
+                .bands()
+                .iter()
+                .map(|band| band.name.to_string())
+                .collect(),
+            violations => panic!("expected one violation, found {:?}", violations),
+        }
+    }
+
+    fn tent() -> Vec<Option<String>> {
+        vec![Some("tent".to_string())]
+    }
+
+    #[test]
+    fn starts_strictly_after_and_at_the_latest_by() {
+        // b starts at noon, one o'clock or two o'clock
+        let starts = [
+            festival(&[], &[&[2]]),
+            festival(&[], &[&[3, 2]]),
+            festival(&[], &[&[3, 4, 2]]),
+        ];
+        let b = named(&starts[0], "b");
+        let after = TimeWindow {
+            band: b.clone(),
+            after: Some(ClockTime::from_minutes_since_noon(60)),
+            before: None,
+        };
+        assert_eq!(broken(&after, &starts[0]), tent());
+        assert_eq!(broken(&after, &starts[1]), tent());
+        assert!(broken(&after, &starts[2]).is_empty());
+        let before = TimeWindow {
+            band: b,
+            after: None,
+            before: Some(ClockTime::from_minutes_since_noon(60)),
+        };
+        assert!(broken(&before, &starts[0]).is_empty());
+        assert!(broken(&before, &starts[1]).is_empty());
+        assert_eq!(broken(&before, &starts[2]), tent());
+    }
+
+    #[test]
+    fn keeps_a_band_out_of_a_venue() {
+        let in_tent = festival(&[], &[&[2]]);
+        let rule = ForbiddenVenue {
+            band: named(&in_tent, "b"),
+            venue: "tent".to_string(),
+        };
+        assert_eq!(broken(&rule, &in_tent), tent());
+        assert!(broken(&rule, &festival(&[], &[&[], &[2]])).is_empty());
+        assert!(broken(&rule, &festival(&[], &[&[3]])).is_empty());
+    }
+
+    #[test]
+    fn keeps_bands_off_each_others_stage() {
+        let shared = festival(&[], &[&[2, 4, 3], &[]]);
+        let rule = NotSameStageAs {
+            band: named(&shared, "b"),
+            others: vec![named(&shared, "c")],
+        };
+        assert_eq!(broken(&rule, &shared), tent());
+        assert_eq!(culprits(&rule, &shared), vec!["b", "c"]);
+        assert!(broken(&rule, &festival(&[], &[&[2, 4], &[3]])).is_empty());
+    }
+
+    #[test]
+    fn requires_the_other_band_when_booked() {
+        let alone = festival(&[], &[&[2]]);
+        let rule = RequiresCoBooking {
+            band: named(&alone, "b"),
+            other: named(&alone, "c"),
+        };
+        assert_eq!(broken(&rule, &alone), vec![None]);
+        assert_eq!(culprits(&rule, &alone), vec!["b", "c"]);
+        assert!(broken(&rule, &festival(&[], &[&[2], &[3]])).is_empty());
+        assert!(broken(&rule, &festival(&[], &[&[3]])).is_empty());
+        // neither band booked
+        assert!(broken(&rule, &festival(&[], &[&[4]])).is_empty());
+    }
+
+    #[test]
+    fn plays_the_last_set_of_its_stages() {
+        let followed = festival(&[], &[&[2, 3], &[]]);
+        let rule = ClosesStage {
+            band: named(&followed, "b"),
+        };
+        assert_eq!(broken(&rule, &followed), tent());
+        assert_eq!(culprits(&rule, &followed), vec!["b", "c"]);
+        assert!(broken(&rule, &festival(&[], &[&[3, 2], &[4]])).is_empty());
+        // an empty stage needs no closer
+        assert!(broken(&rule, &festival(&[], &[&[], &[]])).is_empty());
+        assert!(broken(&rule, &festival(&[], &[&[3], &[]])).is_empty());
+    }
+
+    #[test]
+    fn keeps_bands_apart_on_a_stage() {
+        let next = festival(&[], &[&[3, 2], &[]]);
+        let rule = NotAdjacentTo {
+            band: named(&next, "b"),
+            others: vec![named(&next, "c")],
+        };
+        assert_eq!(broken(&rule, &next), tent());
+        assert_eq!(culprits(&rule, &next), vec!["b", "c"]);
+        assert!(broken(&rule, &festival(&[], &[&[2, 4, 3], &[]])).is_empty());
+        // playing at the same time on different stages is not adjacent
+        assert!(broken(&rule, &festival(&[], &[&[2], &[3]])).is_empty());
+    }
+
+    #[test]
+    fn plays_sets_on_a_stage_back_to_back() {
+        let apart = festival(&[], &[&[0, 2, 1], &[]]);
+        let rule = BackToBack {
+            band: named(&apart, "a"),
+        };
+        assert_eq!(broken(&rule, &apart), tent());
+        assert!(broken(&rule, &festival(&[], &[&[2, 0, 1], &[]])).is_empty());
+        // with one set, or one on each stage
+        assert!(broken(&rule, &festival(&[], &[&[0, 2], &[]])).is_empty());
+        assert!(broken(&rule, &festival(&[], &[&[0, 2], &[3, 1]])).is_empty());
+    }
+
+    #[test]
+    fn plays_every_set_on_one_stage() {
+        let split = festival(&[], &[&[0], &[2, 1]]);
+        let rule = SameStage {
+            band: named(&split, "a"),
+        };
+        assert_eq!(broken(&rule, &split), vec![None]);
+        assert!(broken(&rule, &festival(&[], &[&[0, 2, 1], &[]])).is_empty());
+        // the tent on each day is the same stage
+        let days = ["Friday", "Saturday"];
+        assert!(broken(&rule, &festival(&days, &[&[0], &[], &[1], &[]])).is_empty());
+        assert_eq!(
+            broken(&rule, &festival(&days, &[&[0], &[], &[], &[1]])),
+            vec![None]
+        );
+    }
+
+    #[test]
+    fn rests_between_sets() {
+        let rested = festival(&[], &[&[0, 2, 1], &[]]);
+        let rule = MinGap {
+            band: named(&rested, "a"),
+            minutes: 60,
+        };
+        assert!(broken(&rule, &rested).is_empty());
+        assert_eq!(broken(&rule, &festival(&[], &[&[0, 1], &[]])), vec![None]);
+        // across stages, and with one set
+        assert!(broken(&rule, &festival(&[], &[&[0], &[2, 3, 1]])).is_empty());
+        assert_eq!(
+            broken(&rule, &festival(&[], &[&[2, 0], &[3, 1]])),
+            vec![None]
+        );
+        assert!(broken(&rule, &festival(&[], &[&[0], &[]])).is_empty());
+    }
+
+    #[test]
+    fn plays_each_set_on_another_day() {
+        let days = ["Friday", "Saturday"];
+        let same_day = festival(&days, &[&[0], &[1], &[], &[]]);
+        let rule = DifferentDays {
+            band: named(&same_day, "a"),
+        };
+        assert_eq!(broken(&rule, &same_day), vec![None]);
+        assert!(broken(&rule, &festival(&days, &[&[0], &[], &[], &[1]])).is_empty());
+        assert!(broken(&rule, &festival(&days, &[&[0], &[], &[], &[]])).is_empty());
+    }
+
+    #[test]
+    fn plays_only_on_its_days() {
+        let days = ["Friday", "Saturday"];
+        let friday = festival(&days, &[&[2], &[], &[], &[]]);
+        let rule = AvailableOn {
+            band: named(&friday, "b"),
+            days: vec!["Saturday".to_string()],
+        };
+        assert_eq!(
+            broken(&rule, &friday),
+            vec![Some("tent on Friday".to_string())]
+        );
+        assert!(broken(&rule, &festival(&days, &[&[], &[], &[], &[2]])).is_empty());
+    }
+
+    #[test]
+    fn checks_a_rule_on_one_day() {
+        let days = ["Friday", "Saturday"];
+        let apart = festival(&days, &[&[3], &[], &[2], &[]]);
+        let rule = OnDay {
+            day: "Saturday".to_string(),
+            rule: Box::new(RequiresCoBooking {
+                band: named(&apart, "b"),
+                other: named(&apart, "c"),
+            }),
+        };
+        assert_eq!(broken(&rule, &apart), vec![None]);
+        assert_eq!(
+            rule.violations(&apart, None)[0],
+            Violation::Constraint {
+                rule: "b requires c on Saturday".to_string(),
+                bands: vec![named(&apart, "b"), named(&apart, "c")],
+                venue: None,
+            }
+        );
+        assert!(broken(&rule, &festival(&days, &[&[], &[], &[2], &[3]])).is_empty());
+        assert!(broken(&rule, &festival(&days, &[&[2], &[], &[], &[]])).is_empty());
+        // a rule about a venue's schedule only sees that day's venues
+        let followed = festival(&days, &[&[0], &[], &[1, 3], &[]]);
+        let closes = |day: &str| OnDay {
+            day: day.to_string(),
+            rule: Box::new(ClosesStage {
+                band: named(&followed, "a"),
+            }),
+        };
+        assert!(broken(&closes("Friday"), &followed).is_empty());
+        assert_eq!(
+            broken(&closes("Saturday"), &followed),
+            vec![Some("tent on Saturday".to_string())]
+        );
+    }
+}
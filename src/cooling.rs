@@ -0,0 +1,302 @@
+use crate::solver::neighbour;
+use crate::Econochella;
+use core::fmt;
+use core::str::FromStr;
+use rand::Rng;
+
+/// How the annealing temperature falls over the iterations
+pub trait CoolingSchedule {
+    /// the temperature for the current iteration
+    fn temperature(&self) -> f64;
+    /// advance to the next iteration, given whether this iteration's candidate was accepted
+    fn step(&mut self, accepted: bool);
+}
+
+/// T_k = T_0 * alpha^k
+pub struct Geometric {
+    temperature: f64,
+    alpha: f64,
+}
+
+impl Geometric {
+    pub fn new(initial: f64, alpha: f64) -> Geometric {
+        Geometric {
+            temperature: initial,
+            alpha,
+        }
+    }
+}
+
+impl CoolingSchedule for Geometric {
+    fn temperature(&self) -> f64 {
+        self.temperature
+    }
+    fn step(&mut self, _accepted: bool) {
+        self.temperature *= self.alpha;
+    }
+}
+
+/// T_k = T_0 * (1 - k / iterations), reaching zero at the last iteration
+pub struct Linear {
+    initial: f64,
+    iterations: usize,
+    iteration: usize,
+}
+
+impl Linear {
+    pub fn new(initial: f64, iterations: usize) -> Linear {
+        Linear {
+            initial,
+            iterations: iterations.max(1),
+            iteration: 0,
+        }
+    }
+}
+
+impl CoolingSchedule for Linear {
+    fn temperature(&self) -> f64 {
+        self.initial * (1.0 - self.iteration as f64 / self.iterations as f64).max(0.0)
+    }
+    fn step(&mut self, _accepted: bool) {
+        self.iteration += 1;
+    }
+}
+
+/// T_k = T_0 / ln(k + e), the slow classical schedule
+pub struct Logarithmic {
+    initial: f64,
+    iteration: usize,
+}
+
+impl Logarithmic {
+    pub fn new(initial: f64) -> Logarithmic {
+        Logarithmic {
+            initial,
+            iteration: 0,
+        }
+    }
+}
+
+impl CoolingSchedule for Logarithmic {
+    fn temperature(&self) -> f64 {
+        self.initial / (self.iteration as f64 + std::f64::consts::E).ln()
+    }
+    fn step(&mut self, _accepted: bool) {
+        self.iteration += 1;
+    }
+}
+
+/// Steers the temperature so that a target fraction of candidates is accepted,
+/// while the target itself falls linearly towards zero over the run
+pub struct Adaptive {
+    temperature: f64,
+    /// target acceptance rate at the start of the run
+    initial_target: f64,
+    iterations: usize,
+    iteration: usize,
+    /// iterations between adjustments
+    window: usize,
+    /// accepted candidates in the current window
+    accepted: usize,
+}
+
+impl Adaptive {
+    pub fn new(initial: f64, target: f64, iterations: usize) -> Adaptive {
+        Adaptive {
+            temperature: initial,
+            initial_target: target,
+            iterations: iterations.max(1),
+            iteration: 0,
+            window: (iterations / 50).max(10),
+            accepted: 0,
+        }
+    }
+}
+
+impl CoolingSchedule for Adaptive {
+    fn temperature(&self) -> f64 {
+        self.temperature
+    }
+    fn step(&mut self, accepted: bool) {
+        self.iteration += 1;
+        if accepted {
+            self.accepted += 1;
+        }
+        if self.iteration.is_multiple_of(self.window) {
+            let rate = self.accepted as f64 / self.window as f64;
+            let target =
+                self.initial_target * (1.0 - self.iteration as f64 / self.iterations as f64);
+            if rate > target {
+                self.temperature *= 0.8;
+            } else {
+                self.temperature *= 1.25;
+            }
+            self.accepted = 0;
+        }
+    }
+}
+
+/// The cooling schedules that can be chosen on the command line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScheduleKind {
+    Geometric,
+    Linear,
+    Logarithmic,
+    Adaptive,
+}
+
+impl ScheduleKind {
+    /// build a schedule starting at `initial` for a run of `iterations`
+    pub fn build(self, initial: f64, iterations: usize) -> Box<dyn CoolingSchedule> {
+        match self {
+            // fall to about a thousandth of the initial temperature by the end of the run
+            ScheduleKind::Geometric => Box::new(Geometric::new(
+                initial,
+                0.001f64.powf(1.0 / iterations.max(1) as f64),
+            )),
+            ScheduleKind::Linear => Box::new(Linear::new(initial, iterations)),
+            ScheduleKind::Logarithmic => Box::new(Logarithmic::new(initial)),
+            ScheduleKind::Adaptive => Box::new(Adaptive::new(initial, 0.5, iterations)),
+        }
+    }
+}
+
+impl FromStr for ScheduleKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ScheduleKind, String> {
+        match s {
+            "geometric" => Ok(ScheduleKind::Geometric),
+            "linear" => Ok(ScheduleKind::Linear),
+            "logarithmic" => Ok(ScheduleKind::Logarithmic),
+            "adaptive" => Ok(ScheduleKind::Adaptive),
+            _ => Err(format!(
+                "unknown schedule {}, expected geometric, linear, logarithmic or adaptive",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ScheduleKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ScheduleKind::Geometric => "geometric",
+            ScheduleKind::Linear => "linear",
+            ScheduleKind::Logarithmic => "logarithmic",
+            ScheduleKind::Adaptive => "adaptive",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Pick an initial temperature at which a typical worsening move is accepted
+/// with probability `acceptance`, from the value deltas of a random walk of valid moves
+pub fn calibrate_temperature<R: Rng + ?Sized>(
+    festival: &Econochella,
+    samples: usize,
+    acceptance: f64,
+    rng: &mut R,
+) -> f64 {
+    let mut current = festival.clone();
+    let mut worsening = Vec::new();
+    for _ in 0..samples {
+        let candidate = neighbour(&current, rng);
+        if !candidate.validate().is_empty() {
+            continue;
+        }
+        let delta = candidate.value() - current.value();
+        if delta < 0 {
+            worsening.push(-delta as f64);
+        }
+        current = candidate;
+    }
+    if worsening.is_empty() {
+        return 1.0;
+    }
+    let mean = worsening.iter().sum::<f64>() / worsening.len() as f64;
+    -mean / acceptance.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::toy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// the temperature of each of the schedule's first `steps` iterations
+    fn temperatures(schedule: &mut dyn CoolingSchedule, steps: usize, accepted: bool) -> Vec<f64> {
+        (0..steps)
+            .map(|_| {
+                let temperature = schedule.temperature();
+                schedule.step(accepted);
+                temperature
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cools_geometrically() {
+        let mut schedule = Geometric::new(100.0, 0.5);
+        assert_eq!(
+            temperatures(&mut schedule, 3, true),
+            vec![100.0, 50.0, 25.0]
+        );
+    }
+
+    #[test]
+    fn cools_linearly_to_zero() {
+        let mut schedule = Linear::new(100.0, 4);
+        assert_eq!(
+            temperatures(&mut schedule, 6, false),
+            vec![100.0, 75.0, 50.0, 25.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn cools_logarithmically() {
+        let mut schedule = Logarithmic::new(100.0);
+        let temperatures = temperatures(&mut schedule, 100, false);
+        assert_eq!(temperatures[0], 100.0);
+        assert!(temperatures.windows(2).all(|pair| pair[1] < pair[0]));
+        assert!((temperatures[99] - 100.0 / (99.0 + std::f64::consts::E).ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn adapts_to_the_acceptance_rate() {
+        // a window of 20 iterations, at the end of which the target is 0.49
+        let mut hot = Adaptive::new(100.0, 0.5, 1000);
+        temperatures(&mut hot, 20, true);
+        assert!((hot.temperature() - 80.0).abs() < 1e-9);
+        let mut cold = Adaptive::new(100.0, 0.5, 1000);
+        let before = temperatures(&mut cold, 20, false);
+        assert!(before.iter().all(|&temperature| temperature == 100.0));
+        assert!((cold.temperature() - 125.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn builds_schedules_by_name() {
+        for name in ["geometric", "linear", "logarithmic", "adaptive"] {
+            let kind: ScheduleKind = name.parse().unwrap();
+            assert_eq!(kind.to_string(), name);
+            assert_eq!(kind.build(10.0, 100).temperature(), 10.0);
+        }
+        assert!("exponential".parse::<ScheduleKind>().is_err());
+        let mut geometric = ScheduleKind::Geometric.build(10.0, 100);
+        let last = temperatures(&mut *geometric, 101, true)[100];
+        assert!((last - 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn calibrates_to_the_target_acceptance() {
+        let calibrate = |acceptance| {
+            calibrate_temperature(&toy(), 200, acceptance, &mut StdRng::seed_from_u64(5))
+        };
+        let half = calibrate(0.5);
+        assert!(half > 0.0);
+        // the same walk, so the mean worsening is the same and only the logarithm changes
+        assert!((calibrate(0.8) / half - 0.5f64.ln() / 0.8f64.ln()).abs() < 1e-9);
+        let no_moves = calibrate_temperature(&toy(), 0, 0.5, &mut StdRng::seed_from_u64(5));
+        assert_eq!(no_moves, 1.0);
+    }
+}
@@ -0,0 +1,638 @@
+use crate::names::BandId;
+use crate::violation::Violation;
+use crate::{Band, Econochella, Money, Venue};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Exact branch and bound over band to venue assignments and per-venue orders
+///
+/// Bands are assigned one at a time to a venue or left unused, in order of value per dollar
+///     Prune any assignment over the budget or over a venue's hours
+///     Prune any node whose bound cannot beat the best lineup found so far, where the bound is
+///     the best value of each assigned band at any start time, plus the lesser of the
+///     fractional knapsacks of the remaining bands by budget and by stage time left,
+///     plus the most every bonus could earn
+/// Once every band is assigned, each venue plays its bands in the best order that breaks none
+/// of its rules, found by trying every order (memoized by venue and set of bands), and the
+/// lineup is kept if it is valid and better than the best so far
+///     If it is not, the venues playing a band with more than one set try their other valid
+///     orders too, best first, until their sets follow one another as the rules require
+///
+/// Per-venue orders are chosen independently because a violation or bonus naming a venue
+/// depends only on that venue's schedule, and the rest only on where each set is booked, and
+/// on when the sets start of the bands they name that are booked for more than one
+pub struct BranchAndBound {
+    /// stop after exploring this many nodes, leaving the result unproven
+    pub node_limit: Option<u64>,
+}
+
+/// The outcome of a branch and bound search
+pub struct ExactResult {
+    /// the best valid festival found, if any valid lineup exists
+    pub best: Option<Econochella>,
+    /// nodes explored
+    pub nodes: u64,
+    /// false if the search stopped at the node limit before proving the best optimal
+    pub proven: bool,
+}
+
+impl BranchAndBound {
+    /// find the most valuable valid lineup of the festival's bands, starting from scratch
+    pub fn solve(&self, festival: &Econochella) -> ExactResult {
+        let mut search = Search::new(festival, self.node_limit);
+        search.branch(0);
+        ExactResult {
+            best: search.best,
+            nodes: search.nodes,
+            proven: !search.stopped,
+        }
+    }
+}
+
+/// A quick upper bound on the value of any valid lineup, relaxing ordering and rules
+///
+/// The lesser of the fractional knapsacks of every band's best value at any start time by
+/// budget and by the stage minutes of all venues together, plus the most every bonus could earn,
+/// or none if a bonus gives no bound
+pub fn upper_bound(festival: &Econochella) -> Option<Money> {
+    Some(Search::new(festival, None).bound(0)).filter(|&bound| bound != Money::MAX)
+}
+
+/// A knapsack entry as seen by the search
+struct Item {
+    /// index in the festival's knapsack
+    index: usize,
+    time: u32,
+    cost: Money,
+    /// the band's best value at any start time in any venue
+    max_value: Money,
+    /// the position of the first item playing the same band with the same terms,
+    /// so that interchangeable sets are only assigned in one order
+    group: usize,
+}
+
+/// a venue's most valuable valid order of item groups and its value, if any
+type BestOrder = Option<(Money, Vec<usize>)>;
+
+/// every valid order of a venue's item groups and its value, best first
+type Orders = Rc<Vec<(Money, Vec<usize>)>>;
+
+/// unused, as a venue choice
+const UNUSED: usize = usize::MAX;
+
+struct Search {
+    /// the festival with every band unused
+    base: Econochella,
+    items: Vec<Item>,
+    /// item positions by value per dollar and by value per minute, best first
+    by_cost: Vec<usize>,
+    by_time: Vec<usize>,
+    /// the shortest break of any venue
+    min_break: u32,
+    /// the most all bonuses together could earn
+    bonus_bound: Money,
+    /// bonuses that are a sum over venues, by index
+    per_venue_bonuses: Vec<usize>,
+    /// the best order of each venue's set of item groups and its value, if any order is valid
+    orders: HashMap<(usize, Vec<usize>), BestOrder>,
+    /// every valid order of each venue's set of item groups, for venues whose orders depend
+    /// on one another
+    all_orders: HashMap<(usize, Vec<usize>), Orders>,
+    /// the venue chosen for each assigned item
+    choices: Vec<usize>,
+    /// the item positions assigned to each venue, and the minutes of sets and breaks used
+    assigned: Vec<Vec<usize>>,
+    used_time: Vec<u32>,
+    cost: Money,
+    /// the sum of the assigned items' best values
+    assigned_value: Money,
+    best: Option<Econochella>,
+    best_value: Money,
+    nodes: u64,
+    node_limit: Option<u64>,
+    stopped: bool,
+}
+
+impl Search {
+    fn new(festival: &Econochella, node_limit: Option<u64>) -> Search {
+        let mut base = festival.clone();
+        base.clear();
+        let mut items: Vec<Item> = base
+            .knapsack
+            .iter()
+            .enumerate()
+            .map(|(index, (band, _))| Item {
+                index,
+                time: band.time,
+                cost: band.cost,
+                max_value: base
+                    .venues
+                    .iter()
+                    .filter(|venue| band.time <= venue.total_time)
+                    .flat_map(|venue| {
+                        (0..=venue.total_time - band.time)
+                            .map(move |start| band.value_at(venue.opening_time + start))
+                    })
+                    .max()
+                    .unwrap_or(0),
+                // for now, the first knapsack index of an interchangeable set
+                group: base
+                    .knapsack
+                    .iter()
+                    .position(|(other, _)| {
+                        other.id == band.id
+                            && other.time == band.time
+                            && other.cost == band.cost
+                            && other.revenue == band.revenue
+                    })
+                    .unwrap(),
+            })
+            .collect();
+        // most valuable per dollar first, keeping interchangeable sets together
+        items.sort_by(|a, b| {
+            let density = |item: &Item| item.max_value as f64 / item.cost.max(1) as f64;
+            density(b)
+                .partial_cmp(&density(a))
+                .unwrap()
+                .then_with(|| a.group.cmp(&b.group))
+                .then_with(|| a.index.cmp(&b.index))
+        });
+        let twins: Vec<usize> = items.iter().map(|item| item.group).collect();
+        for (position, item) in items.iter_mut().enumerate() {
+            item.group = twins
+                .iter()
+                .position(|&twin| twin == twins[position])
+                .unwrap();
+        }
+        let mut by_cost: Vec<usize> = (0..items.len()).collect();
+        by_cost.sort_by(|&a, &b| {
+            let density = |item: &Item| item.max_value as f64 / item.cost.max(1) as f64;
+            density(&items[b]).partial_cmp(&density(&items[a])).unwrap()
+        });
+        let min_break = base
+            .venues
+            .iter()
+            .map(|venue| venue.break_time)
+            .min()
+            .unwrap_or(0);
+        let mut by_time: Vec<usize> = (0..items.len()).collect();
+        by_time.sort_by(|&a, &b| {
+            let density =
+                |item: &Item| item.max_value as f64 / (item.time + min_break).max(1) as f64;
+            density(&items[b]).partial_cmp(&density(&items[a])).unwrap()
+        });
+        let bonus_bound = base
+            .bonuses
+            .iter()
+            .map(|bonus| bonus.upper_bound(&base))
+            .fold(0, Money::saturating_add);
+        let per_venue_bonuses = (0..base.bonuses.len())
+            .filter(|&bonus| base.bonuses[bonus].per_venue())
+            .collect();
+        let venue_count = base.venues.len();
+        Search {
+            base,
+            items,
+            by_cost,
+            by_time,
+            min_break,
+            bonus_bound,
+            per_venue_bonuses,
+            orders: HashMap::new(),
+            all_orders: HashMap::new(),
+            choices: Vec::new(),
+            assigned: vec![Vec::new(); venue_count],
+            used_time: vec![0; venue_count],
+            cost: 0,
+            assigned_value: 0,
+            best: None,
+            best_value: Money::MIN,
+            nodes: 0,
+            node_limit,
+            stopped: false,
+        }
+    }
+
+    /// assign the item at `position` and every item after it
+    fn branch(&mut self, position: usize) {
+        if self.stopped {
+            return;
+        }
+        self.nodes += 1;
+        if self.node_limit.is_some_and(|limit| self.nodes > limit) {
+            self.stopped = true;
+            return;
+        }
+        if position == self.items.len() {
+            self.leaf();
+            return;
+        }
+        if self.best.is_some() && self.bound(position) <= self.best_value {
+            return;
+        }
+        let item = &self.items[position];
+        let (time, cost, max_value, group) = (item.time, item.cost, item.max_value, item.group);
+        // interchangeable sets take venues in order, with unused last
+        let first_choice = if group != position {
+            self.choices[position - 1]
+        } else {
+            0
+        };
+        if first_choice != UNUSED && cost + self.cost <= self.base.budget {
+            for venue in first_choice..self.base.venues.len() {
+                let needed = self.set_time(venue, time);
+                if self.used_time[venue] + needed > self.base.venues[venue].total_time {
+                    continue;
+                }
+                self.choices.push(venue);
+                self.assigned[venue].push(position);
+                self.used_time[venue] += needed;
+                self.cost += cost;
+                self.assigned_value += max_value;
+                self.branch(position + 1);
+                self.assigned_value -= max_value;
+                self.cost -= cost;
+                self.used_time[venue] -= needed;
+                self.assigned[venue].pop();
+                self.choices.pop();
+            }
+        }
+        self.choices.push(UNUSED);
+        self.branch(position + 1);
+        self.choices.pop();
+    }
+
+    /// minutes a set of `time` adds to a venue, including the break before it
+    fn set_time(&self, venue: usize, time: u32) -> u32 {
+        if self.assigned[venue].is_empty() {
+            time
+        } else {
+            time + self.base.venues[venue].break_time
+        }
+    }
+
+    /// the most any completion of the current assignment could be worth
+    fn bound(&self, position: usize) -> Money {
+        let budget_left = (self.base.budget - self.cost) as f64;
+        let time_left: u32 = self
+            .base
+            .venues
+            .iter()
+            .zip(&self.used_time)
+            .zip(&self.assigned)
+            .map(|((venue, used), assigned)| {
+                // every set after the first also takes a break, which is counted with the set
+                let first_break = if assigned.is_empty() {
+                    self.min_break
+                } else {
+                    0
+                };
+                venue.total_time + first_break - used
+            })
+            .sum();
+        let by_budget = self.fractional(position, &self.by_cost, budget_left, |item| {
+            item.cost as f64
+        });
+        let by_time = self.fractional(position, &self.by_time, time_left as f64, |item| {
+            (item.time + self.min_break) as f64
+        });
+        (self.assigned_value + by_budget.min(by_time)).saturating_add(self.bonus_bound)
+    }
+
+    /// the fractional knapsack of the items from `position` on, taken in `order`
+    fn fractional(
+        &self,
+        position: usize,
+        order: &[usize],
+        mut capacity: f64,
+        weight: impl Fn(&Item) -> f64,
+    ) -> Money {
+        let mut value = 0.0;
+        for item in order
+            .iter()
+            .filter(|&&other| other >= position)
+            .map(|&other| &self.items[other])
+            .filter(|item| item.max_value > 0)
+        {
+            let weight = weight(item);
+            if weight <= capacity {
+                capacity -= weight;
+                value += item.max_value as f64;
+            } else {
+                value += item.max_value as f64 * capacity.max(0.0) / weight;
+                break;
+            }
+        }
+        value.ceil() as Money
+    }
+
+    /// order every venue's bands and keep the lineup if it is the best so far
+    fn leaf(&mut self) {
+        let mut local_value = 0;
+        let mut orders = Vec::with_capacity(self.assigned.len());
+        for venue in 0..self.assigned.len() {
+            let mut groups: Vec<usize> = self.assigned[venue]
+                .iter()
+                .map(|&position| self.items[position].group)
+                .collect();
+            groups.sort_unstable();
+            match self.best_order(venue, groups) {
+                Some((value, order)) => {
+                    local_value += value;
+                    orders.push(order);
+                }
+                None => return,
+            }
+        }
+        if self.best.is_some() && local_value.saturating_add(self.bonus_bound) <= self.best_value {
+            return;
+        }
+        let violations = self.keep(&orders);
+        // only the start times of bands with more than one set booked depend on the orders
+        // of more than one venue, so other orders can only help if each violation names one
+        let mut sets: HashMap<BandId, usize> = HashMap::new();
+        for venue in &self.assigned {
+            for &position in venue {
+                *sets.entry(self.band(position).id).or_default() += 1;
+            }
+        }
+        let has_sets = |id: &BandId| sets.get(id).is_some_and(|&sets| sets > 1);
+        if violations.is_empty()
+            || !violations
+                .iter()
+                .all(|violation| violation.bands().iter().any(|band| has_sets(&band.id)))
+        {
+            return;
+        }
+        let linked: Vec<usize> = (0..self.assigned.len())
+            .filter(|&venue| {
+                self.assigned[venue]
+                    .iter()
+                    .any(|&position| has_sets(&self.band(position).id))
+            })
+            .collect();
+        let choices: Vec<Orders> = linked
+            .iter()
+            .map(|&venue| {
+                let mut groups: Vec<usize> = self.assigned[venue]
+                    .iter()
+                    .map(|&position| self.items[position].group)
+                    .collect();
+                groups.sort_unstable();
+                self.orders_of(venue, groups)
+            })
+            .collect();
+        // the value of the other venues' best orders
+        let fixed = local_value - choices.iter().map(|orders| orders[0].0).sum::<Money>();
+        self.combine(&linked, &choices, fixed, &mut orders, &mut None);
+    }
+
+    /// try the combinations of orders of the linked venues, best first, keeping any valid
+    /// lineup better than the best so far, where `value` is that of the orders chosen so far
+    /// and `found` that of the best valid combination, which only a more valuable one can beat
+    fn combine(
+        &mut self,
+        linked: &[usize],
+        choices: &[Orders],
+        value: Money,
+        orders: &mut [Vec<usize>],
+        found: &mut Option<Money>,
+    ) {
+        let Some((&venue, rest)) = linked.split_first() else {
+            if self.keep(orders).is_empty() {
+                *found = Some(value);
+            }
+            return;
+        };
+        let bound = choices.iter().map(|orders| orders[0].0).sum::<Money>() + value;
+        if found.is_some_and(|found| bound <= found)
+            || self.best.is_some() && bound.saturating_add(self.bonus_bound) <= self.best_value
+        {
+            return;
+        }
+        for (order_value, order) in choices[0].iter() {
+            orders[venue] = order.clone();
+            self.combine(rest, &choices[1..], value + order_value, orders, found);
+        }
+    }
+
+    /// the band of the item at `position`
+    fn band(&self, position: usize) -> &Band {
+        &self.base.knapsack[self.items[position].index].0
+    }
+
+    /// book the orders, and keep the lineup if it is valid and the best so far,
+    /// returning its violations
+    fn keep(&mut self, orders: &[Vec<usize>]) -> Vec<Violation> {
+        let mut lineup = self.base.clone();
+        for (venue, order) in orders.iter().enumerate() {
+            let mut members = self.assigned[venue].clone();
+            for &group in order {
+                let member = members
+                    .iter()
+                    .position(|&position| self.items[position].group == group)
+                    .unwrap();
+                lineup.book(self.items[members.remove(member)].index, venue);
+            }
+        }
+        let violations = lineup.validate();
+        if !violations.is_empty() {
+            return violations;
+        }
+        let value = lineup.value();
+        if self.best.is_none() || value > self.best_value {
+            self.best_value = value;
+            self.best = Some(lineup);
+        }
+        violations
+    }
+
+    /// the most valuable order of item groups for a venue that breaks none of its rules
+    fn best_order(&mut self, venue: usize, groups: Vec<usize>) -> BestOrder {
+        let key = (venue, groups);
+        if let Some(found) = self.orders.get(&key) {
+            return found.clone();
+        }
+        let mut best: BestOrder = None;
+        self.valid_orders(venue, &key.1, |value, order| {
+            if best.as_ref().is_none_or(|(best, _)| value > *best) {
+                best = Some((value, order.to_vec()));
+            }
+        });
+        self.orders.insert(key, best.clone());
+        best
+    }
+
+    /// every order of item groups for a venue that breaks none of its rules, best first
+    fn orders_of(&mut self, venue: usize, groups: Vec<usize>) -> Orders {
+        let key = (venue, groups);
+        if let Some(found) = self.all_orders.get(&key) {
+            return found.clone();
+        }
+        let mut orders = Vec::new();
+        self.valid_orders(venue, &key.1, |value, order| {
+            orders.push((value, order.to_vec()))
+        });
+        orders.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let orders = Rc::new(orders);
+        self.all_orders.insert(key, orders.clone());
+        orders
+    }
+
+    /// visit each order of item groups for a venue that breaks none of its rules,
+    /// with its value, in lexicographic order
+    fn valid_orders(&self, venue: usize, groups: &[usize], mut visit: impl FnMut(Money, &[usize])) {
+        let mut scratch = self.base.clone();
+        let label = scratch.venues[venue].label();
+        let mut order = groups.to_vec();
+        loop {
+            let mut schedule: Venue = self.base.venues[venue].clone();
+            for &group in &order {
+                let band = &self.base.knapsack[self.items[group].index].0;
+                schedule.push_band(band.clone());
+            }
+            scratch.venues[venue] = schedule;
+            let valid = !scratch
+                .validate()
+                .iter()
+                .any(|violation| violation.venue() == Some(label.as_str()));
+            if valid {
+                let value = scratch.venues[venue].value()
+                    + self
+                        .per_venue_bonuses
+                        .iter()
+                        .map(|&bonus| scratch.bonuses[bonus].value(&scratch))
+                        .sum::<Money>();
+                visit(value, &order);
+            }
+            if !next_permutation(&mut order) {
+                break;
+            }
+        }
+    }
+}
+
+/// rearrange into the next lexicographic permutation, returning false after the last
+fn next_permutation(order: &mut [usize]) -> bool {
+    let Some(pivot) = (1..order.len()).rev().find(|&i| order[i - 1] < order[i]) else {
+        return false;
+    };
+    let successor = (pivot..order.len())
+        .rev()
+        .find(|&i| order[i] > order[pivot - 1])
+        .unwrap();
+    order.swap(pivot - 1, successor);
+    order[pivot..].reverse();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bonuses::Bonus;
+    use crate::clock::ClockTime;
+    use crate::constraints::TimeWindow;
+    use crate::test_support::toy;
+    use std::sync::Arc;
+
+    #[test]
+    fn finds_the_toy_optimum() {
+        let result = BranchAndBound { node_limit: None }.solve(&toy());
+        assert!(result.proven);
+        let best = result.best.unwrap();
+        assert_eq!(best.value(), 220);
+        assert!(best.validate().is_empty());
+    }
+
+    #[test]
+    fn orders_bands_to_satisfy_rules() {
+        let mut festival = toy();
+        festival.constraints = Arc::new(vec![Box::new(TimeWindow {
+            band: festival.names().resolve("a").unwrap(),
+            after: Some(ClockTime::from_minutes_since_noon(30)),
+            before: None,
+        })]);
+        let best = BranchAndBound { node_limit: None }
+            .solve(&festival)
+            .best
+            .unwrap();
+        assert_eq!(best.value(), 220);
+        assert_eq!(best.venues()[0].band_names(), vec!["c", "a"]);
+    }
+
+    #[test]
+    fn bounds_the_toy_by_its_budget() {
+        // a and c fill the budget exactly, while the stage's two hours would fit a and b
+        assert_eq!(upper_bound(&toy()), Some(220));
+    }
+
+    #[test]
+    fn stops_at_the_node_limit() {
+        let result = BranchAndBound {
+            node_limit: Some(2),
+        }
+        .solve(&toy());
+        assert!(!result.proven);
+        assert_eq!(result.nodes, 3);
+    }
+
+    #[test]
+    fn orders_sets_across_stages() {
+        let set = |revenue: Money, time: u32| Band {
+            name: "a".into(),
+            time,
+            revenue,
+            cost: 10,
+            ..Band::default()
+        };
+        // a's first set only fits the tent, so the stage must play b before a's second
+        let festival = Econochella::new(
+            100,
+            vec![
+                Venue::new("tent".to_string(), ClockTime::NOON, 0, 90),
+                Venue::new("stage".to_string(), ClockTime::NOON + 30, 0, 120),
+            ],
+            vec![
+                set(100, 90),
+                set(50, 60),
+                Band {
+                    name: "b".into(),
+                    ..set(40, 60)
+                },
+            ],
+        );
+        let best = BranchAndBound { node_limit: None }
+            .solve(&festival)
+            .best
+            .unwrap();
+        assert_eq!(best.value(), 190 - 30);
+        assert_eq!(best.venues()[1].band_names(), vec!["b", "a"]);
+    }
+
+    /// 100 if c is booked, with none of the optional methods
+    struct BookC;
+
+    impl Bonus for BookC {
+        fn describe(&self) -> String {
+            "100 for booking c".to_string()
+        }
+        fn value(&self, festival: &Econochella) -> Money {
+            let c = festival.names().resolve("c").unwrap().id;
+            if !festival.locations_of(c).is_empty() {
+                100
+            } else {
+                0
+            }
+        }
+    }
+
+    #[test]
+    fn searches_without_pruning_for_an_unbounded_bonus() {
+        let mut festival = toy();
+        festival.bonuses = Arc::new(vec![Box::new(BookC)]);
+        assert_eq!(upper_bound(&festival), None);
+        let result = BranchAndBound { node_limit: None }.solve(&festival);
+        assert!(result.proven);
+        assert_eq!(result.best.unwrap().value(), 320);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::solver::{count_rejection, random_move, SearchResult};
+use crate::{Econochella, Location, Money, TimeSlot};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// A genetic algorithm over whole lineups
+///
+/// Seed the population with random valid walks from the initial festival, then for each generation:
+///     Keep the best individual (elitism)
+///     Fill the rest of the next generation with children of parents picked by binary tournament:
+///         Cross over: each venue takes its schedule from one parent or the other,
+///         skipping bands the child already booked at another venue
+///         Mutate: with probability `mutation_rate`, move a random band to a random location
+///         Repair: drop bands involved in whatever the child breaks (the budget, a venue's hours,
+///         a rule) until it is valid
+///     Record the best and mean value of the generation
+pub struct GeneticAlgorithm {
+    pub generations: usize,
+    /// individuals per generation
+    pub population: usize,
+    /// the chance a child gets a random move after crossover
+    pub mutation_rate: f64,
+}
+
+/// The best and mean value of one generation's population
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Generation {
+    pub best: Money,
+    pub mean: f64,
+}
+
+/// The outcome of a genetic search
+pub struct Evolution {
+    /// the best lineup, the best value after each generation,
+    /// and how many children each check sent to repair
+    pub result: SearchResult,
+    /// the population's statistics after each generation, starting with the seeded one
+    pub generations: Vec<Generation>,
+}
+
+impl GeneticAlgorithm {
+    pub fn solve<R: Rng + ?Sized>(&self, initial: &Econochella, rng: &mut R) -> Evolution {
+        let size = self.population.max(2);
+        let mut rejections = HashMap::new();
+        let mut population: Vec<(Econochella, Money)> = vec![(initial.clone(), initial.value())];
+        while population.len() < size {
+            let individual = random_walk(initial, rng);
+            let value = individual.value();
+            population.push((individual, value));
+        }
+        let mut generations = vec![statistics(&population)];
+        let mut scores = Vec::with_capacity(self.generations);
+
+        for _ in 0..self.generations {
+            let elite = fittest(&population).clone();
+            let mut next = vec![elite];
+            while next.len() < size {
+                let first = &tournament(&population, rng).0;
+                let second = &tournament(&population, rng).0;
+                let mut child = crossover(first, second, rng);
+                if rng.gen_bool(self.mutation_rate.clamp(0.0, 1.0)) {
+                    child = random_move(&child, rng).0;
+                }
+                repair(&mut child, &mut rejections, rng);
+                let value = child.value();
+                next.push((child, value));
+            }
+            population = next;
+            let generation = statistics(&population);
+            scores.push(generation.best);
+            generations.push(generation);
+        }
+        Evolution {
+            result: SearchResult {
+                best: fittest(&population).0.clone(),
+                scores,
+                rejections,
+            },
+            generations,
+        }
+    }
+}
+
+/// the most valuable individual, the first of equals
+fn fittest(population: &[(Econochella, Money)]) -> &(Econochella, Money) {
+    population
+        .iter()
+        .reduce(|best, individual| {
+            if individual.1 > best.1 {
+                individual
+            } else {
+                best
+            }
+        })
+        .expect("the population is never empty")
+}
+
+/// the better of two random individuals
+fn tournament<'a, R: Rng + ?Sized>(
+    population: &'a [(Econochella, Money)],
+    rng: &mut R,
+) -> &'a (Econochella, Money) {
+    let first = population
+        .choose(rng)
+        .expect("the population is never empty");
+    let second = population
+        .choose(rng)
+        .expect("the population is never empty");
+    if second.1 > first.1 {
+        second
+    } else {
+        first
+    }
+}
+
+fn statistics(population: &[(Econochella, Money)]) -> Generation {
+    let total: Money = population.iter().map(|(_, value)| value).sum();
+    Generation {
+        best: fittest(population).1,
+        mean: total as f64 / population.len() as f64,
+    }
+}
+
+/// a valid festival reached by trying one random move per band from the initial one
+fn random_walk<R: Rng + ?Sized>(initial: &Econochella, rng: &mut R) -> Econochella {
+    let mut festival = initial.clone();
+    for _ in 0..festival.knapsack.len() {
+        let candidate = random_move(&festival, rng).0;
+        if candidate.validate().is_empty() {
+            festival = candidate;
+        }
+    }
+    festival
+}
+
+/// a child booking each venue's schedule, in order, from a random one of the parents,
+/// skipping sets the other venues already booked
+pub fn crossover<R: Rng + ?Sized>(
+    first: &Econochella,
+    second: &Econochella,
+    rng: &mut R,
+) -> Econochella {
+    let mut child = first.clone();
+    child.clear();
+    for venue in 0..child.venues.len() {
+        let parent = if rng.gen_bool(0.5) { first } else { second };
+        for time_slot in parent.venues[venue].schedule() {
+            let TimeSlot::BandSlot(_, band) = time_slot else {
+                continue;
+            };
+            if let Some(entry) = child.entry(band.id, band.set) {
+                if child.knapsack[entry].1 == Location::Unused {
+                    child.book(entry, venue);
+                }
+            }
+        }
+    }
+    child
+}
+
+/// remove bookings until the festival is valid, counting the checks it failed
+pub fn repair<R: Rng + ?Sized>(
+    festival: &mut Econochella,
+    rejections: &mut HashMap<String, u32>,
+    rng: &mut R,
+) {
+    let mut violations = festival.validate();
+    if !violations.is_empty() {
+        count_rejection(rejections, &violations);
+    }
+    while let Some(violation) = violations.first() {
+        let venue = violation
+            .venue()
+            .and_then(|name| festival.venue_id(name))
+            .map(Location::Venue);
+        let culprits = violation.bands();
+        let is_culprit = |index: usize| {
+            let id = festival.knapsack[index].0.id;
+            culprits.iter().any(|band| band.id == id)
+        };
+        let mut booked: Vec<usize> = (0..festival.knapsack.len())
+            .filter(|&index| {
+                let (_, location) = &festival.knapsack[index];
+                *location != Location::Unused
+                    && venue.as_ref().is_none_or(|venue| venue == location)
+            })
+            .collect();
+        if booked.iter().any(|&index| is_culprit(index)) {
+            booked.retain(|&index| is_culprit(index));
+        }
+        let Some(&band) = booked.choose(rng) else {
+            // nothing left to remove, so the empty lineup itself is invalid
+            return;
+        };
+        let location = festival.knapsack[band].1.clone();
+        festival.move_band(band, location, Location::Unused, rng);
+        violations = festival.validate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::toy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn finds_the_toy_optimum() {
+        let genetic = GeneticAlgorithm {
+            generations: 30,
+            population: 10,
+            mutation_rate: 0.5,
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let evolution = genetic.solve(&toy(), &mut rng);
+        assert_eq!(evolution.result.best.value(), 220);
+        assert!(evolution.result.best.validate().is_empty());
+        assert_eq!(evolution.result.scores.len(), 30);
+        assert_eq!(evolution.generations.len(), 31);
+    }
+
+    #[test]
+    fn repair_makes_a_child_valid() {
+        let mut festival = toy();
+        for band in 0..3 {
+            festival.book(band, 0);
+        }
+        let mut rejections = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(0);
+        repair(&mut festival, &mut rejections, &mut rng);
+        assert!(festival.validate().is_empty());
+        assert_eq!(rejections.get("over budget"), Some(&1));
+    }
+}
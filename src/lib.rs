@@ -0,0 +1,1069 @@
+//! Econochella: booking bands into festival venues under a budget, venue hours and
+//! booking rules, and searching for the most valuable lineup.
+//!
+//! A festival is described by a TOML [festival file](config::FestivalConfig) naming the
+//! venues, the budget, a tab-separated [roster](roster) of bands and the booking
+//! [rules](rules). [`Econochella::load`] builds the model from one, after which bands are
+//! booked with [`Econochella::move_band`] or [`Econochella::book`], a lineup is checked with
+//! [`Econochella::validate`] and valued with [`Econochella::value`], and a good lineup is
+//! searched for with a [solver](solver).
+//!
+//! A multi-day festival books each venue once per day: "stadium on Friday" and
+//! "stadium on Saturday" are separate venues, each with that day's hours.
+//!
+//! ```no_run
+//! use econochella::Econochella;
+//!
+//! let mut festival = Econochella::load("festival.toml").expect("invalid festival");
+//! let stadium = festival.venue_id("stadium").expect("no stadium");
+//! festival.book(0, stadium);
+//! for violation in festival.validate() {
+//!     println!("{}", violation);
+//! }
+//! println!("value: {}", festival.value());
+//! ```
+
+use core::fmt;
+use rand::prelude::*;
+use std::path::Path;
+
+pub mod bonuses;
+pub mod clock;
+pub mod config;
+pub mod constraints;
+pub mod cooling;
+pub mod exact;
+pub mod genetic;
+pub mod lineup;
+pub mod lp;
+pub mod names;
+pub mod revenue;
+pub mod roster;
+pub mod rules;
+pub mod solver;
+pub mod tabu;
+pub mod tempering;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod violation;
+
+use bonuses::Bonus;
+use clock::ClockTime;
+use config::{ConfigError, FestivalConfig};
+use constraints::Constraint;
+use names::{BandId, BandNames, BandRef};
+use revenue::RevenueCurve;
+use std::sync::Arc;
+use violation::Violation;
+
+/// An amount of dollars, negative for a loss
+pub type Money = i64;
+
+/// A festival: its venues and their schedules, the bands that can be booked, the budget,
+/// and the booking rules and bonuses
+#[derive(Clone)]
+pub struct Econochella {
+    /// the venues, indexed by the id in `Location::Venue`, one per day each is open
+    pub(crate) venues: Vec<Venue>,
+    /// the days of a multi-day festival in order, or none for a single day
+    pub(crate) days: Vec<String>,
+    /// a possible band "card" that can be used, and the corresponding current location
+    pub(crate) knapsack: Vec<(Band, Location)>,
+    /// the name of each band, by id
+    pub(crate) names: Arc<BandNames>,
+    /// total budget
+    pub(crate) budget: Money,
+    /// booking rules that a valid festival satisfies
+    pub(crate) constraints: Arc<Vec<Box<dyn Constraint>>>,
+    /// extra value for combinations of bookings
+    pub(crate) bonuses: Arc<Vec<Box<dyn Bonus>>>,
+}
+
+impl Econochella {
+    /// a festival with every band unused, and no rules or bonuses
+    /// Entries sharing a name are the sets of one band, numbered in the order given
+    pub fn new(budget: Money, venues: Vec<Venue>, bands: Vec<Band>) -> Econochella {
+        let mut names = BandNames::default();
+        let mut knapsack: Vec<(Band, Location)> = Vec::with_capacity(bands.len());
+        for band in bands {
+            let id = names.intern(&band.name);
+            let set = knapsack.iter().filter(|(other, _)| other.id == id).count();
+            let name = names.name(id).clone();
+            knapsack.push((
+                Band {
+                    name,
+                    id,
+                    set,
+                    ..band
+                },
+                Location::Unused,
+            ));
+        }
+        Econochella {
+            venues,
+            days: Vec::new(),
+            knapsack,
+            names: Arc::new(names),
+            budget,
+            constraints: Arc::new(Vec::new()),
+            bonuses: Arc::new(Vec::new()),
+        }
+    }
+    /// build the festival described by a festival file, with every band unused
+    pub fn from_config(
+        config: &FestivalConfig,
+        bands: Vec<Band>,
+    ) -> Result<Econochella, ConfigError> {
+        if config.venues.is_empty() {
+            return Err(ConfigError::Invalid("no venues".to_string()));
+        }
+        for (index, day) in config.days.iter().enumerate() {
+            if config.days[..index].contains(day) {
+                return Err(ConfigError::Invalid(format!("duplicate day {}", day)));
+            }
+        }
+        let mut venue_names: Vec<String> = Vec::new();
+        for venue in &config.venues {
+            if venue_names.contains(&venue.name) {
+                return Err(ConfigError::Invalid(format!(
+                    "duplicate venue {}",
+                    venue.name
+                )));
+            }
+            venue_names.push(venue.name.clone());
+        }
+        let mut venues: Vec<Venue> = Vec::new();
+        if config.days.is_empty() {
+            for venue in &config.venues {
+                if let Some(hours) = venue.hours.first() {
+                    return Err(ConfigError::Invalid(format!(
+                        "the {} has hours on {}, but the festival has no days",
+                        venue.name, hours.day
+                    )));
+                }
+                venues.push(Venue::new(
+                    venue.name.clone(),
+                    venue.opening_time()?,
+                    venue.break_time,
+                    venue.total_time()?,
+                ));
+            }
+        }
+        // venues by day, then in the order of the festival file
+        for day in &config.days {
+            for venue in &config.venues {
+                let (opening_time, total_time) = venue.hours_on(day, &config.days)?;
+                let mut stage_day = Venue::new(
+                    venue.name.clone(),
+                    opening_time,
+                    venue.break_time,
+                    total_time,
+                );
+                stage_day.day = Some(day.clone());
+                venues.push(stage_day);
+            }
+        }
+        let mut festival = Econochella::new(config.budget, venues, bands);
+        let names = festival.names.clone();
+        for curve in &config.revenue_curves {
+            let band = names
+                .resolve(&curve.band)
+                .map_err(|err| ConfigError::Invalid(format!("revenue curve for {}", err)))?;
+            let revenue_curve = curve.build()?;
+            for (set, _) in festival
+                .knapsack
+                .iter_mut()
+                .filter(|(set, _)| set.id == band.id)
+            {
+                set.revenue_curve = revenue_curve.clone();
+            }
+        }
+        let mut constraints = config
+            .constraints
+            .iter()
+            .map(|constraint| constraint.build(&names, &venue_names, &config.days))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(path) = &config.rules {
+            for rule in &config.rule_file {
+                constraints.push(rule.build(path, &names, &venue_names, &config.days)?);
+            }
+        }
+        let bonuses = config
+            .bonuses
+            .iter()
+            .map(|bonus| bonus.build(&names, &config.genres))
+            .collect::<Result<Vec<_>, _>>()?;
+        festival.days = config.days.clone();
+        festival.constraints = Arc::new(constraints);
+        festival.bonuses = Arc::new(bonuses);
+        Ok(festival)
+    }
+    /// load a festival file and the roster it names
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Econochella, ConfigError> {
+        let config = FestivalConfig::load(path)?;
+        let bands = roster::load_bands(&config.roster).map_err(ConfigError::Roster)?;
+        Econochella::from_config(&config, bands)
+    }
+    /// the venues, indexed by the id in `Location::Venue`
+    pub fn venues(&self) -> &[Venue] {
+        &self.venues
+    }
+    /// the days of a multi-day festival in order, empty for a single day
+    pub fn days(&self) -> &[String] {
+        &self.days
+    }
+    /// the id of the venue with this label, such as "stadium" or "stadium on Friday"
+    pub fn venue_id(&self, label: &str) -> Option<usize> {
+        self.venues.iter().position(|venue| venue.label() == label)
+    }
+    /// the ids of the venue with this name on every day
+    pub fn venue_ids(&self, name: &str) -> Vec<usize> {
+        (0..self.venues.len())
+            .filter(|&id| self.venues[id].name == name)
+            .collect()
+    }
+    /// the name of each band, by id
+    pub fn names(&self) -> &BandNames {
+        &self.names
+    }
+    /// the id of the band with this name
+    pub fn band_id(&self, name: &str) -> Option<BandId> {
+        self.names.id(name)
+    }
+    /// every set of every band that can be booked, with its current location
+    pub fn knapsack(&self) -> &[(Band, Location)] {
+        &self.knapsack
+    }
+    /// the knapsack index of a band's set
+    pub fn entry(&self, id: BandId, set: usize) -> Option<usize> {
+        self.knapsack
+            .iter()
+            .position(|(band, _)| band.id == id && band.set == set)
+    }
+    /// the total budget
+    pub fn budget(&self) -> Money {
+        self.budget
+    }
+    /// book a band at the end of a venue's schedule, moving it from where it was
+    pub fn book(&mut self, band_index: usize, venue: usize) {
+        let band = self.knapsack[band_index].0.clone();
+        if let Location::Venue(id) = self.knapsack[band_index].1 {
+            self.venues[id].remove_band(&band);
+        }
+        self.knapsack[band_index].1 = Location::Venue(venue);
+        let id = band.id;
+        self.venues[venue].push_band(band);
+        self.order_sets(id);
+    }
+    /// cancel every booking, leaving every band unused
+    pub fn clear(&mut self) {
+        for venue in &mut self.venues {
+            venue.schedule.clear();
+            venue.current_time = 0;
+        }
+        for (_, location) in &mut self.knapsack {
+            *location = Location::Unused;
+        }
+    }
+    /// the venues open on a day of a multi-day festival, or every venue if none is given
+    pub(crate) fn venues_on<'a>(&'a self, day: Option<&'a str>) -> impl Iterator<Item = &'a Venue> {
+        self.venues
+            .iter()
+            .filter(move |venue| day.is_none() || venue.day() == day)
+    }
+    /// whether any set of the band is booked on the day, or on any day if none is given
+    pub(crate) fn is_booked_on(&self, id: BandId, day: Option<&str>) -> bool {
+        self.venues_on(day).any(|venue| venue.has_band(id))
+    }
+    /// choose a random band, returning its index in the knapsack and its location
+    pub fn choose_band<R: Rng + ?Sized>(&mut self, rng: &mut R) -> (usize, Location) {
+        let len = self.knapsack.len();
+        let index = rng.gen_range(0..len);
+        (index, self.knapsack[index].1.clone())
+    }
+    /// move a band from its current location to a random place in another location
+    pub fn move_band<R: Rng + ?Sized>(
+        &mut self,
+        band_index: usize,
+        original_location: Location,
+        location: Location,
+        rng: &mut R,
+    ) {
+        self.knapsack[band_index].1 = location.clone();
+        let band = &self.knapsack[band_index].0;
+        if let Location::Venue(id) = original_location {
+            self.venues[id].remove_band(band);
+        }
+        let id = band.id;
+        if let Location::Venue(venue) = location {
+            self.venues[venue].add_band(band.clone(), rng);
+        }
+        self.order_sets(id);
+    }
+    /// renumber a band's interchangeable sets, those with the same terms, by start time,
+    /// booking the first of them wherever any is booked
+    fn order_sets(&mut self, id: BandId) {
+        if !self.has_sets() {
+            return;
+        }
+        let entries: Vec<usize> = (0..self.knapsack.len())
+            .filter(|&entry| self.knapsack[entry].0.id == id)
+            .collect();
+        let mut ordered: Vec<usize> = Vec::new();
+        for &first in &entries {
+            if ordered.contains(&first) {
+                continue;
+            }
+            let terms = |entry: usize| {
+                let band = &self.knapsack[entry].0;
+                (band.time, band.cost, band.revenue)
+            };
+            // in set order, as `new` numbers sets in knapsack order
+            let group: Vec<usize> = entries
+                .iter()
+                .copied()
+                .filter(|&entry| terms(entry) == terms(first))
+                .collect();
+            ordered.extend(&group);
+            if group.len() < 2 {
+                continue;
+            }
+            // the (start, venue, schedule index) of each booked set in the group
+            let mut slots: Vec<(u32, usize, usize)> = Vec::new();
+            for &entry in &group {
+                if let Location::Venue(venue) = self.knapsack[entry].1 {
+                    let set = self.knapsack[entry].0.set;
+                    let day = self.day_index(venue) as u32 * ClockTime::MINUTES_PER_DAY;
+                    slots.extend(self.venues[venue].schedule.iter().enumerate().filter_map(
+                        |(index, time_slot)| match time_slot {
+                            TimeSlot::BandSlot(start_time, band)
+                                if band.id == id && band.set == set =>
+                            {
+                                Some((day + start_time.minutes_since_noon(), venue, index))
+                            }
+                            _ => None,
+                        },
+                    ));
+                }
+            }
+            slots.sort_unstable();
+            for (rank, &entry) in group.iter().enumerate() {
+                self.knapsack[entry].1 = match slots.get(rank) {
+                    Some(&(_, venue, index)) => {
+                        // the sets share their terms, so the start times stay the same
+                        if let TimeSlot::BandSlot(_, band) = &mut self.venues[venue].schedule[index]
+                        {
+                            *band = self.knapsack[entry].0.clone();
+                        }
+                        Location::Venue(venue)
+                    }
+                    None => Location::Unused,
+                };
+            }
+        }
+    }
+    /// whether any band plays more than one set
+    fn has_sets(&self) -> bool {
+        self.knapsack.len() > self.names.len()
+    }
+    /// the position of a venue's day among the festival's days, 0 for a single day
+    pub(crate) fn day_index(&self, venue: usize) -> usize {
+        self.venues[venue]
+            .day()
+            .and_then(|day| self.days.iter().position(|known| known == day))
+            .unwrap_or(0)
+    }
+    /// every set booked on the day, or on any day if none is given, as (band, set, start,
+    /// minutes played), sorted, with starts counted in minutes from noon on the first day
+    /// so that sets on different days compare in order
+    pub(crate) fn booked_sets(&self, day: Option<&str>) -> Vec<(BandId, usize, u32, u32)> {
+        let mut sets: Vec<(BandId, usize, u32, u32)> = Vec::new();
+        for (venue_id, venue) in self.venues.iter().enumerate() {
+            if day.is_some_and(|day| venue.day() != Some(day)) {
+                continue;
+            }
+            let day = self.day_index(venue_id) as u32 * ClockTime::MINUTES_PER_DAY;
+            for time_slot in &venue.schedule {
+                if let TimeSlot::BandSlot(start_time, band) = time_slot {
+                    sets.push((
+                        band.id,
+                        band.set,
+                        day + start_time.minutes_since_noon(),
+                        band.time,
+                    ));
+                }
+            }
+        }
+        sets.sort_unstable();
+        sets
+    }
+    /// find every reason the festival is invalid, if any
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        // check the budget
+        let cost = self.cost();
+        if self.budget < cost {
+            violations.push(Violation::OverBudget {
+                cost,
+                budget: self.budget,
+            });
+        }
+        // check that each band's sets are booked from the first
+        for (band, location) in &self.knapsack {
+            if band.set > 0
+                && *location != Location::Unused
+                && self
+                    .entry(band.id, band.set - 1)
+                    .is_some_and(|earlier| self.knapsack[earlier].1 == Location::Unused)
+            {
+                violations.push(Violation::SetSkipped {
+                    band: band.band_ref(),
+                    set: band.set,
+                });
+            }
+        }
+        // check that each band's sets follow one another, each ending before the next starts
+        let sets = if self.has_sets() {
+            self.booked_sets(None)
+        } else {
+            Vec::new()
+        };
+        for pair in sets.windows(2) {
+            let ((id, _, start, time), (next_id, set, next_start, next_time)) = (pair[0], pair[1]);
+            if id != next_id || next_start >= start + time {
+                continue;
+            }
+            let band = BandRef {
+                id,
+                name: self.names.name(id).clone(),
+            };
+            violations.push(if next_start + next_time > start {
+                Violation::SetsOverlap { band, set }
+            } else {
+                Violation::SetOutOfOrder { band, set }
+            });
+        }
+        // check the time
+        for venue in &self.venues {
+            if venue.time() > venue.total_time {
+                violations.push(Violation::OverTime {
+                    venue: venue.label(),
+                    used: venue.time(),
+                    available: venue.total_time,
+                    curfew: venue.curfew(),
+                });
+            }
+        }
+        // check special conditions
+        violations.extend(self.special_conditions());
+        violations
+    }
+    /// check the festival's booking rules
+    pub fn special_conditions(&self) -> Vec<Violation> {
+        self.constraints
+            .iter()
+            .flat_map(|constraint| constraint.violations(self, None))
+            .collect()
+    }
+    /// the value of the festival: each venue's value plus the bonuses
+    pub fn value(&self) -> Money {
+        self.venues.iter().map(Venue::value).sum::<Money>() + self.special_bonuses()
+    }
+    /// find the total cost of all venues' schedules
+    pub fn cost(&self) -> Money {
+        self.venues.iter().map(Venue::cost).sum()
+    }
+    /// the total of the bonuses earned by the current schedules
+    pub fn special_bonuses(&self) -> Money {
+        self.bonuses.iter().map(|bonus| bonus.value(self)).sum()
+    }
+    /// the bonuses earned by the current schedules, skipping those worth nothing
+    pub fn bonus_breakdown(&self) -> Vec<(String, Money)> {
+        self.bonuses
+            .iter()
+            .map(|bonus| (bonus.describe(), bonus.value(self)))
+            .filter(|(_, value)| *value != 0)
+            .collect()
+    }
+    /// all locations the band is booked in, one per booked set
+    pub fn locations_of(&self, id: BandId) -> Vec<Location> {
+        self.knapsack
+            .iter()
+            .filter(|(band, loc)| band.id == id && *loc != Location::Unused)
+            .map(|(_, loc)| loc.clone())
+            .collect()
+    }
+}
+
+/// A stage with opening hours and a schedule of sets and breaks
+#[derive(Clone)]
+pub struct Venue {
+    /// the venue's name
+    pub(crate) name: String,
+    /// the day of a multi-day festival the venue is open with these hours
+    pub(crate) day: Option<String>,
+    /// Opening time
+    pub(crate) opening_time: ClockTime,
+    /// The schedule, consisting of time slots of either a band or a break.
+    pub(crate) schedule: Vec<TimeSlot>,
+    /// Minutes from opening to the end of the last set
+    pub(crate) current_time: u32,
+    /// Total time from opening to the curfew in minutes
+    pub(crate) total_time: u32,
+    /// Standard break time in minutes for this venue
+    pub(crate) break_time: u32,
+}
+
+impl Venue {
+    /// an empty venue
+    pub fn new(name: String, opening_time: ClockTime, break_time: u32, total_time: u32) -> Venue {
+        Venue {
+            name,
+            day: None,
+            opening_time,
+            schedule: Vec::new(),
+            current_time: 0,
+            total_time,
+            break_time,
+        }
+    }
+    /// the venue's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// the day of a multi-day festival
+    pub fn day(&self) -> Option<&str> {
+        self.day.as_deref()
+    }
+    /// the name, and the day of a multi-day festival, such as "stadium on Friday"
+    pub fn label(&self) -> String {
+        match &self.day {
+            Some(day) => format!("{} on {}", self.name, day),
+            None => self.name.clone(),
+        }
+    }
+    /// the schedule of sets and breaks, in order
+    pub fn schedule(&self) -> &[TimeSlot] {
+        &self.schedule
+    }
+    /// the time the first set may start
+    pub fn opening_time(&self) -> ClockTime {
+        self.opening_time
+    }
+    /// the time by which the last set must end
+    pub fn curfew(&self) -> ClockTime {
+        self.opening_time + self.total_time
+    }
+    /// minutes between opening and the curfew
+    pub fn total_time(&self) -> u32 {
+        self.total_time
+    }
+    /// standard break between sets in minutes
+    pub fn break_time(&self) -> u32 {
+        self.break_time
+    }
+    /// add a band after the last set, following a break
+    pub fn push_band(&mut self, band: Band) {
+        if !self.schedule.is_empty() {
+            self.schedule.push(TimeSlot::Break);
+        }
+        self.schedule
+            .push(TimeSlot::BandSlot(ClockTime::NOON, band));
+        self.retime();
+    }
+    /// remove the band's set from the schedule, with the break next to it
+    pub fn remove_band(&mut self, band: &Band) {
+        let Some(index) = self.schedule.iter().position(|time_slot| {
+            matches!(time_slot, TimeSlot::BandSlot(_, b) if b.id == band.id && b.set == band.set)
+        }) else {
+            return;
+        };
+        if self.schedule.len() == 1 {
+            self.schedule.clear();
+        } else if index == 0 {
+            // the first set and the break after it
+            self.schedule.drain(0..2);
+        } else {
+            // the break before the set and the set
+            self.schedule.drain(index - 1..=index);
+        }
+        self.retime();
+    }
+    /// add a band to the schedule in a random place
+    pub fn add_band<R: Rng + ?Sized>(&mut self, band: Band, rng: &mut R) {
+        if self.schedule.is_empty() {
+            self.schedule
+                .push(TimeSlot::BandSlot(ClockTime::NOON, band));
+        } else {
+            // a band slot index, or one past the end
+            let index = rng.gen_range(0..=self.schedule.len().div_ceil(2)) * 2;
+            if index == self.schedule.len() + 1 {
+                // a break after the last band and then the band
+                self.schedule.push(TimeSlot::Break);
+                self.schedule
+                    .push(TimeSlot::BandSlot(ClockTime::NOON, band));
+            } else {
+                // the band and then a break before the band it displaces
+                self.schedule
+                    .insert(index, TimeSlot::BandSlot(ClockTime::NOON, band));
+                self.schedule.insert(index + 1, TimeSlot::Break);
+            }
+        }
+        self.retime();
+    }
+    /// set every start time, and the current time, from the order of the schedule:
+    /// each set starts when the sets and breaks before it end
+    fn retime(&mut self) {
+        let mut time = 0;
+        for time_slot in &mut self.schedule {
+            match time_slot {
+                TimeSlot::BandSlot(start_time, band) => {
+                    *start_time = self.opening_time + time;
+                    time += band.time;
+                }
+                TimeSlot::Break => time += self.break_time,
+            }
+        }
+        self.current_time = time;
+    }
+    /// the names of the scheduled bands in order, skipping breaks
+    pub fn band_names(&self) -> Vec<&str> {
+        self.schedule
+            .iter()
+            .filter_map(|time_slot| match time_slot {
+                TimeSlot::BandSlot(_, band) => Some(&*band.name),
+                TimeSlot::Break => None,
+            })
+            .collect()
+    }
+    /// the ids of the scheduled bands in order, skipping breaks
+    pub fn band_ids(&self) -> Vec<BandId> {
+        self.schedule
+            .iter()
+            .filter_map(|time_slot| match time_slot {
+                TimeSlot::BandSlot(_, band) => Some(band.id),
+                TimeSlot::Break => None,
+            })
+            .collect()
+    }
+    /// the schedule indices and start times of each set by the band
+    pub fn band_slots(&self, id: BandId) -> impl Iterator<Item = (usize, ClockTime)> + '_ {
+        self.schedule
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, time_slot)| match time_slot {
+                TimeSlot::BandSlot(start_time, band) if band.id == id => Some((index, *start_time)),
+                _ => None,
+            })
+    }
+    /// true if the band plays here
+    pub fn has_band(&self, id: BandId) -> bool {
+        self.band_slots(id).next().is_some()
+    }
+    /// find the total cost of the schedule
+    pub fn cost(&self) -> Money {
+        self.schedule
+            .iter()
+            .fold(0, |acc, time_slot| match time_slot {
+                TimeSlot::BandSlot(_, band) => acc + band.cost,
+                TimeSlot::Break => acc,
+            })
+    }
+    /// find the total time of the schedule
+    pub fn time(&self) -> u32 {
+        self.schedule
+            .iter()
+            .fold(0, |acc, time_slot| match time_slot {
+                TimeSlot::BandSlot(_, band) => acc + band.time,
+                TimeSlot::Break => acc + self.break_time,
+            })
+    }
+
+    /// find the value of the schedule, negative if it loses money
+    pub fn value(&self) -> Money {
+        self.schedule
+            .iter()
+            .fold(0, |acc, time_slot| match time_slot {
+                TimeSlot::BandSlot(start_time, band) => acc + band.value_at(*start_time),
+                TimeSlot::Break => acc,
+            })
+    }
+}
+
+impl fmt::Display for Venue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.label())?;
+        writeln!(
+            f,
+            "    Hours: {}\u{2013}{}",
+            self.opening_time,
+            self.curfew()
+        )?;
+        writeln!(f, "    Time used: {}/{}", self.time(), self.total_time)?;
+        writeln!(f, "    Cost: {}", self.cost())?;
+        writeln!(f, "    Value: {}", self.value())?;
+        // a break runs from the end of the set before it
+        let mut end = self.opening_time;
+        for el in &self.schedule {
+            match el {
+                TimeSlot::BandSlot(start_time, band) => {
+                    end = *start_time + band.time;
+                    writeln!(f, "    {}\u{2013}{} {}", start_time, end, band.label())?
+                }
+                TimeSlot::Break => {
+                    writeln!(f, "    {}\u{2013}{} Break", end, end + self.break_time)?
+                }
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Where a band is booked
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// the id of a venue in `Econochella::venues`
+    Venue(usize),
+    Unused,
+}
+
+impl Location {
+    /// choose one of `venue_count` venues or unused, uniformly
+    pub fn choose_location<R: Rng + ?Sized>(&self, venue_count: usize, rng: &mut R) -> Location {
+        match rng.gen_range(0..=venue_count) {
+            id if id < venue_count => Location::Venue(id),
+            _ => Location::Unused,
+        }
+    }
+}
+
+/// An entry in a venue's schedule
+#[derive(Clone, Debug)]
+pub enum TimeSlot {
+    Break,
+    /// (start time, band)
+    BandSlot(ClockTime, Band),
+}
+
+/// One set that a band can be booked to play; a band playing several sets has one per set
+#[derive(Clone, Debug, Default)]
+pub struct Band {
+    /// Name of band, shared by its sets
+    pub name: Arc<str>,
+    /// Time to play in minutes
+    pub time: u32,
+    /// Anticipated revenue in dollars
+    /// (if this were not accounted for, we would not have econochella at all. we would simply pocket the budget)
+    pub revenue: Money,
+    /// Cost in dollars
+    pub cost: Money,
+    /// Revenue multiplier by the time the set starts
+    pub revenue_curve: RevenueCurve,
+    /// The band's id, shared by all its sets, assigned by `Econochella::new`
+    pub(crate) id: BandId,
+    /// Which of the band's sets this is, from 0; a later set is only booked with the ones before it
+    pub(crate) set: usize,
+}
+
+impl Band {
+    /// the band's id, the same for each of its sets
+    pub fn id(&self) -> BandId {
+        self.id
+    }
+    /// which of the band's sets this is, from 0
+    pub fn set(&self) -> usize {
+        self.set
+    }
+    /// the band's id and name, as rules and violations refer to it
+    pub fn band_ref(&self) -> BandRef {
+        BandRef {
+            id: self.id,
+            name: self.name.clone(),
+        }
+    }
+    /// the name, followed by the set for every set after the first, such as "Infu$ion (set 2)"
+    pub fn label(&self) -> String {
+        if self.set == 0 {
+            self.name.to_string()
+        } else {
+            format!("{} (set {})", self.name, self.set + 1)
+        }
+    }
+    /// find the value of the band playing a set starting at this time
+    pub fn value_at(&self, time: ClockTime) -> Money {
+        (self.revenue as f64 * self.revenue_curve.multiplier(time)).round() as Money - self.cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints::MinGap;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn band(name: &str, time: u32) -> Band {
+        Band {
+            name: name.into(),
+            time,
+            revenue: 100,
+            cost: 10,
+            ..Band::default()
+        }
+    }
+
+    /// sets alternate with single breaks, and each starts when everything before it ends
+    fn assert_consistent(venue: &Venue) {
+        let mut time = 0;
+        for (index, time_slot) in venue.schedule.iter().enumerate() {
+            match time_slot {
+                TimeSlot::BandSlot(start_time, band) => {
+                    assert_eq!(index % 2, 0, "set at odd index {}", index);
+                    assert_eq!(
+                        *start_time,
+                        venue.opening_time + time,
+                        "{} starts at the wrong time",
+                        band.name
+                    );
+                    time += band.time;
+                }
+                TimeSlot::Break => {
+                    assert_eq!(index % 2, 1, "break at even index {}", index);
+                    time += venue.break_time;
+                }
+            }
+        }
+        assert_eq!(venue.schedule.len() % 2, venue.schedule.len().min(1));
+        assert_eq!(venue.current_time, time);
+        assert_eq!(venue.time(), time);
+    }
+
+    #[test]
+    fn random_adds_and_removes_keep_start_times_consistent() {
+        let bands: Vec<Band> = (0..8)
+            .map(|index| band(&format!("band {}", index), 20 + 15 * index))
+            .collect();
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut venue = Venue::new("stage".to_string(), "13:00".parse().unwrap(), 10, 600);
+            let mut booked: Vec<usize> = Vec::new();
+            for _ in 0..200 {
+                let index = rng.gen_range(0..bands.len());
+                if let Some(position) = booked.iter().position(|&booked| booked == index) {
+                    booked.remove(position);
+                    venue.remove_band(&bands[index]);
+                } else if rng.gen_bool(0.5) {
+                    booked.push(index);
+                    venue.add_band(bands[index].clone(), &mut rng);
+                } else {
+                    booked.push(index);
+                    venue.push_band(bands[index].clone());
+                }
+                assert_consistent(&venue);
+                assert_eq!(venue.band_names().len(), booked.len());
+            }
+        }
+    }
+
+    #[test]
+    fn books_each_venue_once_per_day() {
+        let config: FestivalConfig = toml::from_str(
+            r#"
+            budget = 100
+            roster = "bands.tsv"
+            days = ["Friday", "Saturday"]
+            constraints = [{ kind = "different_days", band = "a" }]
+
+            [[venues]]
+            name = "stage"
+            opens = "17:00"
+            closes = "22:00"
+            break = 0
+            hours = [{ day = "Saturday", opens = "13:00", curfew = "01:00" }]
+            "#,
+        )
+        .unwrap();
+        let mut festival =
+            Econochella::from_config(&config, vec![band("a", 60), band("a", 60)]).unwrap();
+        let friday = festival.venue_id("stage on Friday").unwrap();
+        let saturday = festival.venue_id("stage on Saturday").unwrap();
+        assert_eq!(festival.venue_ids("stage"), vec![friday, saturday]);
+        assert_eq!(festival.venues()[friday].total_time(), 5 * 60);
+        assert_eq!(festival.venues()[saturday].total_time(), 12 * 60);
+        assert_eq!(festival.venues()[saturday].curfew().to_string(), "01:00");
+        festival.book(0, friday);
+        festival.book(1, saturday);
+        assert!(festival.validate().is_empty());
+        festival.move_band(
+            1,
+            Location::Venue(saturday),
+            Location::Venue(friday),
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(festival.validate().len(), 1);
+    }
+
+    #[test]
+    fn reports_unknown_names_where_they_are() {
+        let mut config: FestivalConfig = toml::from_str(
+            r#"
+            budget = 100
+            roster = "bands.tsv"
+            rules = "bands.rules"
+
+            [[venues]]
+            name = "tent"
+            opens = "17:00"
+            closes = "22:00"
+            break = 0
+            "#,
+        )
+        .unwrap();
+        let mut error = |rules: &str| {
+            config.rule_file = rules::parse_rules(rules).unwrap();
+            Econochella::from_config(&config, vec![band("a", 60)])
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        // at the venue's column, after the indent, band and keyword
+        assert_eq!(
+            error("# typos\n\n  \"a\" not_in tnet\n\"a\" closes_stage\n"),
+            "bands.rules:3:14: constraint refers to unknown venue tnet"
+        );
+        assert_eq!(
+            error("\"a\" never_same_stage_as \"a\", \"A\""),
+            "bands.rules:1:30: constraint refers to unknown band A; did you mean a?"
+        );
+    }
+
+    #[test]
+    fn books_a_bands_sets_in_order() {
+        let second = Band {
+            revenue: 50,
+            ..band("a", 30)
+        };
+        let mut festival = Econochella::new(
+            100,
+            vec![Venue::new("stage".to_string(), ClockTime::NOON, 0, 120)],
+            vec![band("a", 30), second, band("b", 30)],
+        );
+        let labels: Vec<String> = festival
+            .knapsack()
+            .iter()
+            .map(|(band, _)| band.label())
+            .collect();
+        assert_eq!(labels, vec!["a", "a (set 2)", "b"]);
+        assert_eq!(festival.entry(BandId(0), 1), Some(1));
+        assert_eq!(festival.entry(BandId(1), 0), Some(2));
+        festival.book(1, 0);
+        assert_eq!(
+            festival.validate(),
+            vec![Violation::SetSkipped {
+                band: festival.knapsack()[1].0.band_ref(),
+                set: 1
+            }]
+        );
+        festival.book(0, 0);
+        assert_eq!(
+            festival.validate(),
+            vec![Violation::SetOutOfOrder {
+                band: festival.knapsack()[1].0.band_ref(),
+                set: 1
+            }]
+        );
+        // booking the second set again puts it after the first
+        festival.book(1, 0);
+        assert!(festival.validate().is_empty());
+        assert_eq!(festival.value(), 50 + 100 - 20);
+        // removing the first set leaves the second in place
+        festival.move_band(
+            0,
+            Location::Venue(0),
+            Location::Unused,
+            &mut StdRng::seed_from_u64(0),
+        );
+        match festival.venues()[0].schedule() {
+            [TimeSlot::BandSlot(_, band)] => assert_eq!(band.label(), "a (set 2)"),
+            schedule => panic!("unexpected schedule {:?}", schedule),
+        }
+    }
+
+    /// two sets of a, a stage opening at noon and another an hour and a half later
+    fn two_stages() -> Econochella {
+        Econochella::new(
+            100,
+            vec![
+                Venue::new("stage".to_string(), ClockTime::NOON, 0, 120),
+                Venue::new("tent".to_string(), ClockTime::NOON + 90, 0, 120),
+            ],
+            vec![band("a", 60), band("a", 60), band("b", 60)],
+        )
+    }
+
+    #[test]
+    fn rejects_overlapping_sets_on_different_stages() {
+        let mut festival = two_stages();
+        festival.book(2, 1);
+        festival.book(0, 0);
+        festival.book(1, 1);
+        assert!(festival.validate().is_empty());
+        // b opens the stage, so a's first set ends at two, after its second starts
+        festival.book(2, 0);
+        festival.book(0, 0);
+        assert_eq!(
+            festival.validate(),
+            vec![Violation::SetsOverlap {
+                band: festival.knapsack()[1].0.band_ref(),
+                set: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn numbers_sets_by_start_time() {
+        let mut festival = two_stages();
+        festival.book(0, 1);
+        festival.book(1, 0);
+        assert_eq!(festival.venues()[0].band_names(), vec!["a"]);
+        assert_eq!(festival.knapsack()[0].1, Location::Venue(0));
+        assert_eq!(festival.knapsack()[1].1, Location::Venue(1));
+        match festival.venues()[1].schedule() {
+            [TimeSlot::BandSlot(start, band)] => {
+                assert_eq!(*start, ClockTime::NOON + 90);
+                assert_eq!(band.label(), "a (set 2)");
+            }
+            schedule => panic!("unexpected schedule {:?}", schedule),
+        }
+        assert!(festival.validate().is_empty());
+        festival.move_band(
+            0,
+            Location::Venue(0),
+            Location::Unused,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(festival.knapsack()[0].1, Location::Venue(1));
+        assert_eq!(festival.knapsack()[1].1, Location::Unused);
+        assert!(festival.validate().is_empty());
+    }
+
+    #[test]
+    fn rests_between_sets_on_different_stages() {
+        let mut festival = two_stages();
+        festival.book(0, 0);
+        festival.book(1, 1);
+        let a = festival.names().resolve("a").unwrap();
+        for (minutes, rested) in [(30, true), (31, false)] {
+            festival.constraints = Arc::new(vec![Box::new(MinGap {
+                band: a.clone(),
+                minutes,
+            })]);
+            assert_eq!(
+                festival.validate().is_empty(),
+                rested,
+                "{} minutes",
+                minutes
+            );
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Lineup files: a schedule written by hand or saved by `econochella solve`,
+//! listing each venue's sets in the order they play:
+//!
+//! ```toml
+//! [[venues]]
+//! name = "stadium"
+//! bands = ["Macaulay & Co.", "Infu$ion", "Infu$ion (set 2)"]
+//! ```
+//!
+//! Sets are separated by each venue's standard break, and venues left out play nothing.
+//! A band's name alone books its first set not yet booked, and "(set 2)" after it a particular set.
+//! In a multi-day festival each venue is listed once per day, with `day = "Saturday"`.
+
+use crate::{Econochella, Location, TimeSlot};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The bands booked into each venue
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lineup {
+    #[serde(default)]
+    pub venues: Vec<VenueLineup>,
+}
+
+/// A venue's sets in order
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueLineup {
+    pub name: String,
+    /// the day of a multi-day festival
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<String>,
+    #[serde(default)]
+    pub bands: Vec<String>,
+}
+
+/// An error encountered while reading a lineup file
+#[derive(Debug)]
+pub enum LineupError {
+    /// the file could not be read
+    Io(io::Error),
+    /// the file is not valid TOML or does not match the expected layout
+    Parse(toml::de::Error),
+    /// the lineup names venues or bands the festival does not have
+    Invalid(String),
+}
+
+impl fmt::Display for LineupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LineupError::Io(err) => write!(f, "unable to read lineup: {}", err),
+            LineupError::Parse(err) => write!(f, "invalid lineup: {}", err),
+            LineupError::Invalid(message) => write!(f, "invalid lineup: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for LineupError {
+    fn from(err: io::Error) -> LineupError {
+        LineupError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for LineupError {
+    fn from(err: toml::de::Error) -> LineupError {
+        LineupError::Parse(err)
+    }
+}
+
+impl Lineup {
+    /// Load a lineup file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Lineup, LineupError> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+    /// the lineup currently booked in a festival, listing every venue
+    pub fn of(festival: &Econochella) -> Lineup {
+        Lineup {
+            venues: festival
+                .venues()
+                .iter()
+                .map(|venue| VenueLineup {
+                    name: venue.name().to_string(),
+                    day: venue.day().map(str::to_string),
+                    bands: venue
+                        .schedule()
+                        .iter()
+                        .filter_map(|time_slot| match time_slot {
+                            TimeSlot::BandSlot(_, band) => Some(band.label()),
+                            TimeSlot::Break => None,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+    /// the lineup as the contents of a lineup file
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("a lineup is always representable in TOML")
+    }
+    /// a copy of the festival with every band unused and then this lineup booked
+    pub fn apply(&self, festival: &Econochella) -> Result<Econochella, LineupError> {
+        let mut booked = festival.clone();
+        booked.clear();
+        let mut seen: Vec<usize> = Vec::new();
+        // the sets listed by label, which may be booked under another number once
+        // the sets are numbered by start time
+        let mut labels: Vec<&str> = Vec::new();
+        for venue in &self.venues {
+            let label = match &venue.day {
+                Some(day) => format!("{} on {}", venue.name, day),
+                None => venue.name.clone(),
+            };
+            let id = booked.venue_id(&label).ok_or_else(|| {
+                if venue.day.is_none() && !booked.days().is_empty() {
+                    LineupError::Invalid(format!("venue {} needs a day", venue.name))
+                } else {
+                    LineupError::Invalid(format!("unknown venue {}", label))
+                }
+            })?;
+            if seen.contains(&id) {
+                return Err(LineupError::Invalid(format!(
+                    "venue {} is listed twice",
+                    label
+                )));
+            }
+            seen.push(id);
+            for name in &venue.bands {
+                let named = booked.band_id(name);
+                if labels.contains(&name.as_str()) {
+                    return Err(LineupError::Invalid(format!("{} is listed twice", name)));
+                }
+                if named.is_none() {
+                    labels.push(name);
+                }
+                let band = booked
+                    .knapsack()
+                    .iter()
+                    .position(|(band, location)| {
+                        (named == Some(band.id()) || &band.label() == name)
+                            && *location == Location::Unused
+                    })
+                    .ok_or_else(|| {
+                        if named.is_some() {
+                            LineupError::Invalid(format!(
+                                "{} is booked for more sets than the roster lists",
+                                name
+                            ))
+                        } else if booked
+                            .knapsack()
+                            .iter()
+                            .any(|(band, _)| &band.label() == name)
+                        {
+                            LineupError::Invalid(format!("{} is listed twice", name))
+                        } else {
+                            LineupError::Invalid(booked.names().unknown(name).to_string())
+                        }
+                    })?;
+                booked.book(band, id);
+            }
+        }
+        Ok(booked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ClockTime;
+    use crate::test_support::band;
+    use crate::{Band, Venue};
+
+    /// a tent and a stage, and bands a (two sets, the second worth less) and b
+    fn festival() -> Econochella {
+        let stage = |name: &str| Venue::new(name.to_string(), ClockTime::NOON, 0, 300);
+        Econochella::new(
+            100,
+            vec![stage("tent"), stage("stage")],
+            vec![
+                band("a", 100, 10),
+                Band {
+                    revenue: 50,
+                    ..band("a", 100, 10)
+                },
+                band("b", 100, 10),
+            ],
+        )
+    }
+
+    /// the festival with a lineup file's contents applied
+    fn apply(contents: &str) -> Result<Econochella, LineupError> {
+        toml::from_str::<Lineup>(contents)?.apply(&festival())
+    }
+
+    /// the message of a lineup's error
+    fn invalid(contents: &str) -> String {
+        match apply(contents) {
+            Err(LineupError::Invalid(message)) => message,
+            Err(err) => panic!("expected an invalid lineup, found {}", err),
+            Ok(_) => panic!("expected an invalid lineup"),
+        }
+    }
+
+    #[test]
+    fn reads_back_the_lineup_it_writes() {
+        let mut booked = festival();
+        booked.book(0, 0);
+        booked.book(2, 1);
+        booked.book(1, 0);
+        assert!(booked.validate().is_empty());
+        let written = Lineup::of(&booked).to_toml();
+        let read = apply(&written).unwrap();
+        assert_eq!(Lineup::of(&read).to_toml(), written);
+        let locations = |festival: &Econochella| -> Vec<Location> {
+            festival
+                .knapsack()
+                .iter()
+                .map(|(_, location)| location.clone())
+                .collect()
+        };
+        assert_eq!(locations(&read), locations(&booked));
+        assert_eq!(read.value(), booked.value());
+    }
+
+    #[test]
+    fn books_the_set_a_label_names() {
+        let second = apply(
+            r#"
+            [[venues]]
+            name = "tent"
+            bands = ["b", "a (set 2)"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(second.knapsack()[0].1, Location::Unused);
+        assert_eq!(second.knapsack()[1].1, Location::Venue(0));
+        // a name alone books the first set not yet booked
+        let both = apply(
+            r#"
+            [[venues]]
+            name = "tent"
+            bands = ["a (set 2)"]
+
+            [[venues]]
+            name = "stage"
+            bands = ["a"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(both.knapsack()[0].1, Location::Venue(1));
+        assert_eq!(both.knapsack()[1].1, Location::Venue(0));
+    }
+
+    #[test]
+    fn rejects_a_set_listed_twice() {
+        assert_eq!(
+            invalid(
+                r#"
+                [[venues]]
+                name = "tent"
+                bands = ["a (set 2)", "b", "a (set 2)"]
+                "#
+            ),
+            "a (set 2) is listed twice"
+        );
+        assert_eq!(
+            invalid(
+                r#"
+                [[venues]]
+                name = "tent"
+                bands = ["b"]
+
+                [[venues]]
+                name = "stage"
+                bands = ["b"]
+                "#
+            ),
+            "b is booked for more sets than the roster lists"
+        );
+        assert_eq!(
+            invalid(
+                r#"
+                [[venues]]
+                name = "tent"
+
+                [[venues]]
+                name = "tent"
+                "#
+            ),
+            "venue tent is listed twice"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert_eq!(
+            invalid(
+                r#"
+                [[venues]]
+                name = "tent"
+                bands = ["B"]
+                "#
+            ),
+            "unknown band B; did you mean b?"
+        );
+        assert_eq!(
+            invalid(
+                r#"
+                [[venues]]
+                name = "tnet"
+                "#
+            ),
+            "unknown venue tnet"
+        );
+        assert_eq!(
+            invalid(
+                r#"
+                [[venues]]
+                name = "tent"
+                day = "Sunday"
+                "#
+            ),
+            "unknown venue tent on Sunday"
+        );
+        assert!(matches!(
+            apply("[[venues]]\nname = \"tent\"\nbands = \"a\"\n"),
+            Err(LineupError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn needs_the_day_of_a_multi_day_festival() {
+        let mut festival = festival();
+        for venue in &mut festival.venues {
+            venue.day = Some("Friday".to_string());
+        }
+        festival.days = vec!["Friday".to_string()];
+        let lineup = |day: &str| -> Lineup {
+            toml::from_str(&format!(
+                "[[venues]]\nname = \"tent\"\n{}bands = [\"b\"]\n",
+                day
+            ))
+            .unwrap()
+        };
+        let booked = lineup("day = \"Friday\"\n").apply(&festival).unwrap();
+        assert_eq!(booked.knapsack()[2].1, Location::Venue(0));
+        match lineup("").apply(&festival) {
+            Err(LineupError::Invalid(message)) => assert_eq!(message, "venue tent needs a day"),
+            other => panic!("expected a missing day, found {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,702 @@
+//! Export a festival as a mixed integer program in CPLEX LP format, and import a solver's
+//! solution back into a lineup.
+//!
+//! Binary `x_b{i}_v{j}_p{k}` is 1 if the band's set at index `i` of the knapsack is the `k`-th set
+//! (from 0) at venue `j`. Each venue fills its positions from the first, `s_v{j}_p{k}` is the
+//! start of its `k`-th set in minutes after opening, and `t_b{i}` the clock start of the band's
+//! set in minutes since noon, where a rule or revenue curve needs it. Booking rules and bonuses
+//! add their own rows through [`Constraint::linearize`](crate::constraints::Constraint::linearize)
+//! and [`Bonus::linearize`](crate::bonuses::Bonus::linearize).
+
+use crate::clock::ClockTime;
+use crate::names::BandId;
+use crate::{Econochella, Money};
+use core::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The direction of a linear constraint
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sense {
+    AtMost,
+    AtLeast,
+    Equal,
+}
+
+impl fmt::Display for Sense {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sense = match self {
+            Sense::AtMost => "<=",
+            Sense::AtLeast => ">=",
+            Sense::Equal => "=",
+        };
+        write!(f, "{}", sense)
+    }
+}
+
+/// A linear expression as (coefficient, variable) terms
+pub type Terms = Vec<(Money, String)>;
+
+/// A mixed integer program of a festival, built up row by row
+pub struct LinearModel<'a> {
+    festival: &'a Econochella,
+    /// the most sets each venue could hold
+    positions: Vec<usize>,
+    objective: Terms,
+    rows: Vec<(String, Terms, Sense, Money)>,
+    binaries: Vec<String>,
+    /// whether each band's start time variable has been linked to its sets yet
+    starts: Vec<bool>,
+    /// descriptions of the rules and bonuses that could not be expressed
+    skipped: Vec<String>,
+}
+
+impl<'a> LinearModel<'a> {
+    /// the assignment, ordering, time and budget rows of a festival, and its objective
+    fn new(festival: &'a Econochella) -> LinearModel<'a> {
+        let shortest = festival
+            .knapsack
+            .iter()
+            .map(|(band, _)| band.time)
+            .min()
+            .unwrap_or(0);
+        let positions = festival
+            .venues
+            .iter()
+            .map(|venue| {
+                ((venue.total_time + venue.break_time) / (shortest + venue.break_time).max(1))
+                    as usize
+            })
+            .collect();
+        let mut model = LinearModel {
+            festival,
+            positions,
+            objective: Vec::new(),
+            rows: Vec::new(),
+            binaries: Vec::new(),
+            starts: vec![false; festival.knapsack.len()],
+            skipped: Vec::new(),
+        };
+        let all: Vec<usize> = (0..festival.knapsack.len()).collect();
+        for entry in 0..festival.knapsack.len() {
+            let booked = model.booked(entry);
+            model.named_row(format!("once_b{}", entry), booked, Sense::AtMost, 1);
+            // a later set is only booked with the set before it
+            let band = &festival.knapsack[entry].0;
+            if let Some(earlier) = band
+                .set
+                .checked_sub(1)
+                .and_then(|set| festival.entry(band.id, set))
+            {
+                let mut order = model.booked(entry);
+                order.extend(model.booked(earlier).into_iter().map(|(_, x)| (-1, x)));
+                model.named_row(format!("set_b{}", entry), order, Sense::AtMost, 0);
+                // and starts once the set before it has ended, on any stage or day
+                let m = model.big_m();
+                let mut after = model.sequence_start(entry);
+                after.extend(
+                    model
+                        .sequence_start(earlier)
+                        .into_iter()
+                        .map(|(coefficient, variable)| (-coefficient, variable)),
+                );
+                after.extend(model.booked(entry).into_iter().map(|(_, x)| (-m, x)));
+                let time = festival.knapsack[earlier].0.time as Money;
+                model.named_row(format!("after_b{}", entry), after, Sense::AtLeast, time - m);
+            }
+        }
+        for venue in 0..festival.venues.len() {
+            let break_time = festival.venues[venue].break_time;
+            let set_time = |entry: usize| (festival.knapsack[entry].0.time + break_time) as Money;
+            for position in 0..model.positions[venue] {
+                let slot = model.at(&all, venue, position);
+                model.named_row(
+                    format!("slot_v{}_p{}", venue, position),
+                    slot,
+                    Sense::AtMost,
+                    1,
+                );
+                if position == 0 {
+                    continue;
+                }
+                // a position is only filled if the one before it is
+                let mut order = model.at(&all, venue, position);
+                order.extend(
+                    model
+                        .at(&all, venue, position - 1)
+                        .into_iter()
+                        .map(|(_, x)| (-1, x)),
+                );
+                model.named_row(
+                    format!("order_v{}_p{}", venue, position),
+                    order,
+                    Sense::AtMost,
+                    0,
+                );
+                // each set starts after the one before it and its break
+                let mut start = vec![(1, model.s(venue, position))];
+                if position > 1 {
+                    start.push((-1, model.s(venue, position - 1)));
+                }
+                start.extend(
+                    all.iter()
+                        .map(|&entry| (-set_time(entry), model.x(entry, venue, position - 1))),
+                );
+                model.named_row(
+                    format!("start_v{}_p{}", venue, position),
+                    start,
+                    Sense::Equal,
+                    0,
+                );
+            }
+            // every set takes its time and a break, except that the first needs no break
+            let sets = &model;
+            let time = (0..model.positions[venue])
+                .flat_map(|position| {
+                    all.iter()
+                        .map(move |&entry| (set_time(entry), sets.x(entry, venue, position)))
+                })
+                .collect();
+            model.named_row(
+                format!("time_v{}", venue),
+                time,
+                Sense::AtMost,
+                (festival.venues[venue].total_time + break_time) as Money,
+            );
+        }
+        let cost = all
+            .iter()
+            .flat_map(|&entry| {
+                let cost = festival.knapsack[entry].0.cost;
+                model.booked(entry).into_iter().map(move |(_, x)| (cost, x))
+            })
+            .collect();
+        model.named_row("budget".to_string(), cost, Sense::AtMost, festival.budget);
+        for entry in 0..festival.knapsack.len() {
+            model.band_value(entry);
+        }
+        model
+    }
+
+    /// add the band's value to the objective, choosing a piece of its revenue curve
+    fn band_value(&mut self, entry: usize) {
+        let festival = self.festival;
+        let band = &festival.knapsack[entry].0;
+        let segments = band.revenue_curve.segments();
+        if segments.len() == 1 {
+            let value = band.value_at(ClockTime::NOON);
+            for (_, x) in self.booked(entry) {
+                self.add_objective(value, x);
+            }
+            return;
+        }
+        let start = self.start(entry);
+        let m = self.big_m();
+        let mut pieces: Terms = self
+            .booked(entry)
+            .into_iter()
+            .map(|(_, x)| (-1, x))
+            .collect();
+        for (from, until, _) in segments {
+            let z = self.add_binary(&format!("z_b{}_", entry));
+            pieces.push((1, z.clone()));
+            self.add_objective(band.value_at(from), z.clone());
+            if from > ClockTime::NOON {
+                self.add_row(
+                    vec![(1, start.clone()), (-m, z.clone())],
+                    Sense::AtLeast,
+                    from.minutes_since_noon() as Money - m,
+                );
+            }
+            if let Some(until) = until {
+                self.add_row(
+                    vec![(1, start.clone()), (m, z)],
+                    Sense::AtMost,
+                    until.minutes_since_noon() as Money - 1 + m,
+                );
+            }
+        }
+        self.named_row(format!("piece_b{}", entry), pieces, Sense::Equal, 0);
+    }
+
+    /// the knapsack indices of every set the band could play
+    pub fn entries(&self, band: BandId) -> Vec<usize> {
+        (0..self.festival.knapsack.len())
+            .filter(|&entry| self.festival.knapsack[entry].0.id == band)
+            .collect()
+    }
+    /// the knapsack indices of every set not played by one of these bands
+    pub fn entries_except(&self, bands: &[BandId]) -> Vec<usize> {
+        (0..self.festival.knapsack.len())
+            .filter(|&entry| !bands.contains(&self.festival.knapsack[entry].0.id))
+            .collect()
+    }
+    pub fn venue_count(&self) -> usize {
+        self.festival.venues.len()
+    }
+    /// the venue's name, the same on every day
+    pub fn venue_name(&self, venue: usize) -> &str {
+        self.festival.venues[venue].name()
+    }
+    /// the ids of the venue with this name on every day
+    pub fn venue_ids(&self, name: &str) -> Vec<usize> {
+        self.festival.venue_ids(name)
+    }
+    /// the day of a multi-day festival the venue is open
+    pub fn day(&self, venue: usize) -> Option<String> {
+        self.festival.venues[venue].day.clone()
+    }
+    /// the minutes the set at `entry` plays
+    pub fn set_time(&self, entry: usize) -> u32 {
+        self.festival.knapsack[entry].0.time
+    }
+    /// the most sets the venue could hold
+    pub fn positions(&self, venue: usize) -> usize {
+        self.positions[venue]
+    }
+    /// a constant larger than any time difference in the model
+    pub fn big_m(&self) -> Money {
+        (self.festival.days.len().max(1) as Money + 1) * ClockTime::MINUTES_PER_DAY as Money
+    }
+    /// the variable for the band at `entry` playing the venue's set at `position`
+    pub fn x(&self, entry: usize, venue: usize, position: usize) -> String {
+        format!("x_b{}_v{}_p{}", entry, venue, position)
+    }
+    /// the variable for the start of the venue's set at `position`, after opening
+    fn s(&self, venue: usize, position: usize) -> String {
+        format!("s_v{}_p{}", venue, position)
+    }
+    /// 1 for each set at which the band at `entry` could play: the sum is 1 if it is booked
+    pub fn booked(&self, entry: usize) -> Terms {
+        (0..self.venue_count())
+            .flat_map(|venue| {
+                (0..self.positions[venue]).map(move |position| (1, self.x(entry, venue, position)))
+            })
+            .collect()
+    }
+    /// 1 for each of these entries at a venue's set: the sum is 1 if one of them plays it
+    pub fn at(&self, entries: &[usize], venue: usize, position: usize) -> Terms {
+        if position >= self.positions[venue] {
+            return Vec::new();
+        }
+        entries
+            .iter()
+            .map(|&entry| (1, self.x(entry, venue, position)))
+            .collect()
+    }
+    /// the variable for the start of the band's set in minutes since noon, if it is booked
+    pub fn start(&mut self, entry: usize) -> String {
+        let start = format!("t_b{}", entry);
+        if self.starts[entry] {
+            return start;
+        }
+        self.starts[entry] = true;
+        let m = self.big_m();
+        for venue in 0..self.venue_count() {
+            let opening_time = self.festival.venues[venue]
+                .opening_time
+                .minutes_since_noon() as Money;
+            for position in 0..self.positions[venue] {
+                let x = self.x(entry, venue, position);
+                // start = opening + s when the band plays this set
+                for (sense, sign) in [(Sense::AtLeast, -1), (Sense::AtMost, 1)] {
+                    let mut terms = vec![(1, start.clone()), (sign * m, x.clone())];
+                    if position > 0 {
+                        terms.push((-1, self.s(venue, position)));
+                    }
+                    self.add_row(terms, sense, opening_time + sign * m);
+                }
+            }
+        }
+        start
+    }
+    /// the start of the band's set in minutes since noon on the festival's first day,
+    /// if it is booked, so that sets on different days compare in order
+    pub fn sequence_start(&mut self, entry: usize) -> Terms {
+        let mut terms = vec![(1, self.start(entry))];
+        for venue in 0..self.venue_count() {
+            let day = self.festival.day_index(venue) as Money * ClockTime::MINUTES_PER_DAY as Money;
+            if day > 0 {
+                terms.extend(
+                    (0..self.positions[venue])
+                        .map(|position| (day, self.x(entry, venue, position))),
+                );
+            }
+        }
+        terms
+    }
+    /// a new binary variable named from `prefix`
+    pub fn add_binary(&mut self, prefix: &str) -> String {
+        let name = format!("{}{}", prefix, self.binaries.len());
+        self.binaries.push(name.clone());
+        name
+    }
+    /// add `coefficient * variable` to the value maximized
+    pub fn add_objective(&mut self, coefficient: Money, variable: String) {
+        self.objective.push((coefficient, variable));
+    }
+    /// add the row `terms sense rhs`
+    pub fn add_row(&mut self, terms: Terms, sense: Sense, rhs: Money) {
+        let name = format!("c{}", self.rows.len());
+        self.named_row(name, terms, sense, rhs);
+    }
+    fn named_row(&mut self, name: String, terms: Terms, sense: Sense, rhs: Money) {
+        self.rows.push((name, terms, sense, rhs));
+    }
+
+    /// the model in CPLEX LP format
+    fn write(&self) -> String {
+        let mut lp = String::new();
+        lp.push_str("\\ Econochella lineup\n");
+        lp.push_str("\\ x_b{i}_v{j}_p{k} = 1 if band i plays the k-th set at venue j\n");
+        for (entry, (band, _)) in self.festival.knapsack.iter().enumerate() {
+            lp.push_str(&format!("\\ b{}: {}\n", entry, band.label()));
+        }
+        for (id, venue) in self.festival.venues.iter().enumerate() {
+            lp.push_str(&format!("\\ v{}: {}\n", id, venue.label()));
+        }
+        for skipped in &self.skipped {
+            lp.push_str(&format!("\\ not exported: {}\n", skipped));
+        }
+        lp.push_str("Maximize\n value:");
+        write_terms(&mut lp, &self.objective);
+        lp.push_str("\nSubject To\n");
+        for (name, terms, sense, rhs) in &self.rows {
+            lp.push_str(&format!(" {}:", name));
+            write_terms(&mut lp, terms);
+            lp.push_str(&format!(" {} {}\n", sense, rhs));
+        }
+        lp.push_str("Binaries\n");
+        for venue in 0..self.venue_count() {
+            for position in 0..self.positions[venue] {
+                for entry in 0..self.festival.knapsack.len() {
+                    lp.push_str(&format!(" {}\n", self.x(entry, venue, position)));
+                }
+            }
+        }
+        for binary in &self.binaries {
+            lp.push_str(&format!(" {}\n", binary));
+        }
+        lp.push_str("End\n");
+        lp
+    }
+}
+
+/// append terms, a few to a line
+fn write_terms(lp: &mut String, terms: &[(Money, String)]) {
+    if terms.is_empty() {
+        // an empty expression is not valid LP, so use a zero multiple of some variable
+        lp.push_str(" 0 x_b0_v0_p0");
+        return;
+    }
+    for (index, (coefficient, variable)) in terms.iter().enumerate() {
+        if index > 0 && index % 8 == 0 {
+            lp.push_str("\n   ");
+        }
+        let sign = if *coefficient < 0 { '-' } else { '+' };
+        if index == 0 && *coefficient >= 0 {
+            lp.push_str(&format!(" {} {}", coefficient, variable));
+        } else {
+            lp.push_str(&format!(" {} {} {}", sign, coefficient.abs(), variable));
+        }
+    }
+}
+
+/// Write the festival's assignment and sequencing problem in CPLEX LP format
+/// Rules and bonuses that cannot be written as linear rows are listed in a comment
+pub fn export_lp(festival: &Econochella) -> String {
+    model(festival).write()
+}
+
+/// the festival's model with every rule and bonus that can be expressed
+fn model(festival: &Econochella) -> LinearModel<'_> {
+    let mut model = LinearModel::new(festival);
+    for constraint in festival.constraints.iter() {
+        linearize(&mut model, constraint.describe(), |model| {
+            constraint.linearize(model)
+        });
+    }
+    for bonus in festival.bonuses.iter() {
+        linearize(&mut model, bonus.describe(), |model| bonus.linearize(model));
+    }
+    model
+}
+
+/// add a rule or bonus to the model, or note that it was left out
+fn linearize<'a>(
+    model: &mut LinearModel<'a>,
+    description: String,
+    add: impl FnOnce(&mut LinearModel<'a>) -> bool,
+) {
+    let rows = model.rows.len();
+    let binaries = model.binaries.len();
+    let objective = model.objective.len();
+    if !add(model) {
+        model.rows.truncate(rows);
+        model.binaries.truncate(binaries);
+        model.objective.truncate(objective);
+        model.skipped.push(description);
+    }
+}
+
+/// An error encountered while reading a solution file
+#[derive(Debug)]
+pub enum SolutionError {
+    /// the file could not be read
+    Io(io::Error),
+    /// a line names a set that does not exist or is already taken, with the 1-based line number
+    Malformed { line: usize, message: String },
+}
+
+impl fmt::Display for SolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolutionError::Io(err) => write!(f, "unable to read solution: {}", err),
+            SolutionError::Malformed { line, message } => {
+                write!(f, "solution line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SolutionError {
+    fn from(err: io::Error) -> SolutionError {
+        SolutionError::Io(err)
+    }
+}
+
+/// Load a solver's solution file and book it into a copy of the festival
+pub fn load_solution<P: AsRef<Path>>(
+    festival: &Econochella,
+    path: P,
+) -> Result<Econochella, SolutionError> {
+    import_solution(festival, &fs::read_to_string(path)?)
+}
+
+/// Book the sets set to 1 in a solution into a copy of the festival with every band unused
+/// Any line naming an `x_b{i}_v{j}_p{k}` variable followed by its value is read, which covers
+/// `name value` listings and CBC's solution files; other lines are ignored
+pub fn import_solution(festival: &Econochella, text: &str) -> Result<Econochella, SolutionError> {
+    let mut sets: Vec<(usize, usize, usize)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let malformed = |message: String| SolutionError::Malformed {
+            line: index + 1,
+            message,
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(at) = tokens.iter().position(|token| parse_x(token).is_some()) else {
+            continue;
+        };
+        let (entry, venue, position) = parse_x(tokens[at]).unwrap();
+        let value: f64 = tokens
+            .get(at + 1)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| malformed(format!("missing value for {}", tokens[at])))?;
+        if value < 0.5 {
+            continue;
+        }
+        if entry >= festival.knapsack.len() || venue >= festival.venues.len() {
+            return Err(malformed(format!(
+                "no such band or venue in {}",
+                tokens[at]
+            )));
+        }
+        if sets.iter().any(|set| set.0 == entry) {
+            return Err(malformed(format!(
+                "{} is booked more than once",
+                festival.knapsack[entry].0.label()
+            )));
+        }
+        if sets.iter().any(|set| set.1 == venue && set.2 == position) {
+            return Err(malformed(format!(
+                "set {} of the {} is booked more than once",
+                position,
+                festival.venues[venue].label()
+            )));
+        }
+        sets.push((entry, venue, position));
+    }
+    sets.sort_by_key(|&(_, venue, position)| (venue, position));
+    let mut booked = festival.clone();
+    booked.clear();
+    for (entry, venue, _) in sets {
+        booked.book(entry, venue);
+    }
+    Ok(booked)
+}
+
+/// the (entry, venue, position) of an `x_b{i}_v{j}_p{k}` variable
+fn parse_x(name: &str) -> Option<(usize, usize, usize)> {
+    let rest = name.strip_prefix("x_b")?;
+    let (entry, rest) = rest.split_once("_v")?;
+    let (venue, position) = rest.split_once("_p")?;
+    Some((
+        entry.parse().ok()?,
+        venue.parse().ok()?,
+        position.parse().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints::{Constraint, MinGap};
+    use crate::test_support::{band, toy};
+    use crate::violation::Violation;
+    use crate::{TimeSlot, Venue};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn exports_budget_and_time_rows() {
+        let lp = export_lp(&toy());
+        assert!(lp.contains(" budget: 60 x_b0_v0_p0 + 60 x_b0_v0_p1 + 50 x_b1_v0_p0"));
+        assert!(lp.contains(" time_v0: 60 x_b0_v0_p0 + 60 x_b1_v0_p0 + 60 x_b2_v0_p0"));
+        assert!(lp.ends_with("End\n"));
+    }
+
+    #[test]
+    fn imports_sets_in_position_order() {
+        let solution = "x_b2_v0_p0 1\nx_b1_v0_p0 0\n  3 x_b0_v0_p1  1  0\n";
+        let booked = import_solution(&toy(), solution).unwrap();
+        assert_eq!(booked.venues()[0].band_names(), vec!["c", "a"]);
+        assert_eq!(booked.value(), 220);
+    }
+
+    #[test]
+    fn rejects_a_set_booked_twice() {
+        let solution = "x_b0_v0_p0 1\nx_b1_v0_p0 1\n";
+        assert!(matches!(
+            import_solution(&toy(), solution),
+            Err(SolutionError::Malformed { line: 2, .. })
+        ));
+    }
+
+    /// the value of each variable of the model for the festival's current lineup, with the
+    /// start of a set no one plays just after the set before it, and unbooked bands at noon
+    fn variables(festival: &Econochella) -> HashMap<String, Money> {
+        let model = LinearModel::new(festival);
+        let mut values = HashMap::new();
+        for (venue_id, venue) in festival.venues().iter().enumerate() {
+            let mut start = 0;
+            let mut sets = venue
+                .schedule()
+                .iter()
+                .filter_map(|time_slot| match time_slot {
+                    TimeSlot::BandSlot(start_time, band) => Some((start_time, band)),
+                    TimeSlot::Break => None,
+                });
+            for position in 0..model.positions(venue_id) {
+                if position > 0 {
+                    values.insert(model.s(venue_id, position), start);
+                }
+                if let Some((start_time, band)) = sets.next() {
+                    let entry = festival.entry(band.id(), band.set()).unwrap();
+                    values.insert(model.x(entry, venue_id, position), 1);
+                    values.insert(
+                        format!("t_b{}", entry),
+                        start_time.minutes_since_noon() as Money,
+                    );
+                    start += (band.time + venue.break_time) as Money;
+                }
+            }
+        }
+        values
+    }
+
+    /// the rows the variables break, and the objective's value
+    fn evaluate(model: &LinearModel, values: &HashMap<String, Money>) -> (Vec<String>, Money) {
+        let sum = |terms: &Terms| -> Money {
+            terms
+                .iter()
+                .map(|(coefficient, variable)| coefficient * values.get(variable).unwrap_or(&0))
+                .sum()
+        };
+        let broken = model
+            .rows
+            .iter()
+            .filter(|(_, terms, sense, rhs)| match sense {
+                Sense::AtMost => sum(terms) > *rhs,
+                Sense::AtLeast => sum(terms) < *rhs,
+                Sense::Equal => sum(terms) != *rhs,
+            })
+            .map(|(name, ..)| name.clone())
+            .collect();
+        (broken, sum(&model.objective))
+    }
+
+    /// two stages, and a's two sets, b and c, with a resting an hour between sets
+    fn two_stages() -> Econochella {
+        let mut festival = Econochella::new(
+            1000,
+            vec![
+                Venue::new("tent".to_string(), ClockTime::NOON, 10, 180),
+                Venue::new("stage".to_string(), ClockTime::NOON + 60, 0, 150),
+            ],
+            vec![
+                band("a", 200, 60),
+                band("a", 150, 50),
+                band("b", 120, 40),
+                band("c", 90, 30),
+            ],
+        );
+        let a = festival.names().resolve("a").unwrap();
+        festival.constraints = Arc::new(vec![Box::new(MinGap {
+            band: a,
+            minutes: 60,
+        })]);
+        festival
+    }
+
+    #[test]
+    fn values_a_valid_lineup_as_the_festival_does() {
+        let mut festival = two_stages();
+        // a at noon, then b in the tent; c at one, then a at two on the stage
+        festival.book(0, 0);
+        festival.book(2, 0);
+        festival.book(3, 1);
+        festival.book(1, 1);
+        assert!(festival.validate().is_empty());
+        let model = model(&festival);
+        let (broken, objective) = evaluate(&model, &variables(&festival));
+        assert!(broken.is_empty(), "broken rows {:?}", broken);
+        assert_eq!(objective, festival.value());
+    }
+
+    #[test]
+    fn breaks_a_row_for_each_invalid_lineup() {
+        // a's second set overlaps its first, or follows it without a rest
+        for (tent, stage) in [([2, 0], [1, 3]), ([0, 2], [1, 3])] {
+            let mut festival = two_stages();
+            for entry in tent {
+                festival.book(entry, 0);
+            }
+            for entry in stage {
+                festival.book(entry, 1);
+            }
+            assert!(!festival.validate().is_empty());
+            let (broken, _) = evaluate(&model(&festival), &variables(&festival));
+            assert!(!broken.is_empty());
+        }
+    }
+
+    /// a rule with no linear form
+    struct Anything;
+
+    impl Constraint for Anything {
+        fn describe(&self) -> String {
+            "anything goes".to_string()
+        }
+        fn violations(&self, _festival: &Econochella, _day: Option<&str>) -> Vec<Violation> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn notes_rules_that_are_not_exported() {
+        let mut festival = toy();
+        festival.constraints = Arc::new(vec![Box::new(Anything)]);
+        assert!(export_lp(&festival).contains("\\ not exported: anything goes\n"));
+    }
+}
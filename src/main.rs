@@ -1,771 +1,756 @@
-use core::fmt;
+use econochella::config::{ConfigError, FestivalConfig};
+use econochella::cooling::{calibrate_temperature, ScheduleKind};
+use econochella::exact::{upper_bound, BranchAndBound};
+use econochella::genetic::{Evolution, GeneticAlgorithm};
+use econochella::lineup::Lineup;
+use econochella::lp::{export_lp, load_solution};
+use econochella::roster;
+use econochella::solver::{SearchResult, SimulatedAnnealing};
+use econochella::tabu::TabuSearch;
+use econochella::tempering::{Exchange, ParallelTempering};
+use econochella::violation::Violation;
+use econochella::{Econochella, Money, TimeSlot};
 use rand::prelude::*;
+use std::fmt::Display;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
+use std::process;
+use std::str::FromStr;
 
-const TEMPERATURE: f64 = 0.5;
+/// number of random moves sampled to calibrate the initial temperature
+const CALIBRATION_SAMPLES: usize = 200;
 
-#[derive(Clone)]
-struct Econochella {
-    tent: Venue,
-    amphitheater: Venue,
-    stadium: Venue,
-    /// a possible band "card" that can be used, and the corresponding current location
-    knapsack: Vec<(Band, Location)>,
-    /// total budget
-    budget: u32,
-    /// temperature controls tradeoff between exploration and exploitation
-    temperature: f64,
+/// the command succeeded, and any lineup checked is valid
+const EXIT_OK: i32 = 0;
+/// something went wrong that is not the fault of the input, such as failing to write output
+const EXIT_INTERNAL: i32 = 1;
+/// the command line could not be understood
+const EXIT_USAGE: i32 = 2;
+/// a festival, roster, rules or lineup file could not be read or is malformed
+const EXIT_INVALID_INPUT: i32 = 3;
+/// the input is well formed but the lineup breaks the budget, venue hours or rules
+const EXIT_INFEASIBLE: i32 = 4;
+
+const USAGE: &str = "usage:
+    econochella solve <festival.toml> [--roster FILE] [--budget DOLLARS] [--iterations N]
+        [--seed N] [--solver annealing|tabu|genetic|tempering]
+        [--schedule geometric|linear|logarithmic|adaptive] [--temperature T]
+        [--chains N] [--swap-interval N]
+        [--tenure N] [--candidates N]
+        [--generations N] [--population N] [--mutation-rate P]
+        [--format text|lineup] [--scores FILE] [--certify [--node-limit N]]
+    econochella validate <festival.toml> <lineup.toml>
+    econochella explain <festival.toml> <lineup.toml>
+    econochella export <festival.toml>
+    econochella import <festival.toml> <solution> [--format text|lineup]
+
+exit codes: 0 success, 1 internal error, 2 usage error, 3 invalid input file,
+    4 infeasible lineup";
+
+/// How `solve` prints the best lineup found
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    /// a human readable report
+    Text,
+    /// a lineup file that `validate` and `explain` accept
+    Lineup,
 }
 
-impl Econochella {
-    fn new(budget: u32, temperature: f64, bands: Vec<Band>) -> Econochella {
-        let knapsack = bands
-            .iter()
-            .map(|band| (band.clone(), Location::Unused))
-            .collect();
-        Econochella {
-            tent: Venue::new("tent".to_string(), 15, 300),
-            amphitheater: Venue::new("amphitheater".to_string(), 30, 360),
-            stadium: Venue::new("stadium".to_string(), 30, 360),
-            knapsack,
-            budget,
-            temperature,
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "lineup" => Ok(Format::Lineup),
+            _ => Err(format!("unknown format {}, expected text or lineup", s)),
         }
     }
-    fn choose_band(&mut self, rng: &mut ThreadRng) -> (usize, Location) {
-        let len = self.knapsack.len();
-        let index = rng.gen_range(0..len);
-        (index, self.knapsack[index].1.clone())
-    }
-    fn move_band(
-        &mut self,
-        band_index: usize,
-        original_location: Location,
-        location: Location,
-        rng: &mut ThreadRng,
-    ) {
-        self.knapsack[band_index].1 = location.clone();
-        let band = &self.knapsack[band_index].0;
-        match original_location {
-            Location::Tent => self.tent.remove_band(band),
-            Location::Amphitheater => self.amphitheater.remove_band(band),
-            Location::Stadium => self.stadium.remove_band(band),
-            Location::Unused => (),
-        }
-        match location {
-            Location::Tent => self.tent.add_band(band.clone(), rng),
-            Location::Amphitheater => self.amphitheater.add_band(band.clone(), rng),
-            Location::Stadium => self.stadium.add_band(band.clone(), rng),
-            Location::Unused => (),
-        }
-    }
-    fn valid(&self) -> bool {
-        // check the budget
-        if self.budget < self.amphitheater.cost() + self.stadium.cost() + self.tent.cost() {
-            return false;
-        }
-        // check the time
-        if self.tent.time() > self.tent.total_time
-            || self.amphitheater.time() > self.amphitheater.total_time
-            || self.stadium.time() > self.stadium.total_time
-        {
-            return false;
-        }
-        // check special conditions
-        if !self.special_conditions() {
-            return false;
+}
+
+/// The search `solve` runs
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SolverKind {
+    Annealing,
+    Tabu,
+    Genetic,
+    Tempering,
+}
+
+impl FromStr for SolverKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<SolverKind, String> {
+        match s {
+            "annealing" => Ok(SolverKind::Annealing),
+            "tabu" => Ok(SolverKind::Tabu),
+            "genetic" => Ok(SolverKind::Genetic),
+            "tempering" => Ok(SolverKind::Tempering),
+            _ => Err(format!(
+                "unknown solver {}, expected annealing, tabu, genetic or tempering",
+                s
+            )),
         }
-        true
     }
-    /// check's several special conditions
-    fn special_conditions(&self) -> bool {
-        let macy_dynamite = {
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Macy Dynamite")
-                .unwrap()
-                .clone()
-                .1;
-            if let Some(TimeSlot::BandSlot(start_time, _)) =
-                self.get_schedule(&loc).iter().find(|slot| {
-                    if let TimeSlot::BandSlot(_, band) = slot {
-                        &band.name == "Macy Dynamite"
-                    } else {
-                        false
-                    }
-                })
-            {
-                // must play after 9 pm if playing
-                time_is_after(9 * 60, *start_time, loc)
-            } else {
-                // otherwise tru
-                true
-            }
-        };
-        let illiterate_monkeys = {
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Illiterate Monkeys")
-                .unwrap()
-                .clone()
-                .1;
-            !self.any_same_stage(&loc, vec!["Fractured Coccyx", "Macaulay & Co."])
-        };
-        let onyx_eyes = {
-            // chicken fried awesome must also be playing
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Onyx Eyes")
-                .unwrap()
-                .clone()
-                .1;
-            if loc == Location::Unused {
-                true
-            } else {
-                // chicken fried awesome must be booked in some venue
-                self.amphitheater.schedule.iter().any(|a| {
-                    if let TimeSlot::BandSlot(_, band) = a {
-                        &band.name == "Chicken Fried Awesome"
-                    } else {
-                        false
-                    }
-                }) || self.stadium.schedule.iter().any(|a| {
-                    if let TimeSlot::BandSlot(_, band) = a {
-                        &band.name == "Chicken Fried Awesome"
-                    } else {
-                        false
-                    }
-                }) || self.tent.schedule.iter().any(|a| {
-                    if let TimeSlot::BandSlot(_, band) = a {
-                        &band.name == "Chicken Fried Awesome"
-                    } else {
-                        false
+}
+
+/// The subcommand and its arguments
+enum Command {
+    /// search for the most valuable valid lineup
+    Solve(SolveArgs),
+    /// check a lineup file against the festival
+    Validate { festival: String, lineup: String },
+    /// break down a lineup's value by venue, band and bonus
+    Explain { festival: String, lineup: String },
+    /// write the festival as a mixed integer program in CPLEX LP format
+    Export { festival: String },
+    /// read a solver's solution to the exported program and check it
+    Import {
+        festival: String,
+        solution: String,
+        format: Format,
+    },
+}
+
+/// Arguments to `solve`
+struct SolveArgs {
+    /// path to the festival file
+    festival: String,
+    /// roster to use instead of the one the festival file names
+    roster: Option<String>,
+    /// budget to use instead of the festival file's
+    budget: Option<Money>,
+    /// number of search iterations
+    iterations: usize,
+    /// seed for the random number generator
+    seed: Option<u64>,
+    /// which search to run
+    solver: SolverKind,
+    /// how the annealing temperature falls
+    schedule: ScheduleKind,
+    /// initial annealing temperature, calibrated from sampled moves if not given
+    temperature: Option<f64>,
+    /// parallel tempering chains, one per thread; the same on every host so that seeds replay
+    chains: usize,
+    /// iterations between parallel tempering's swap attempts
+    swap_interval: usize,
+    /// iterations for which tabu search keeps a band from returning to a location it left
+    tenure: usize,
+    /// moves tabu search samples per iteration
+    candidates: usize,
+    /// generations the genetic algorithm breeds
+    generations: usize,
+    /// lineups in each generation
+    population: usize,
+    /// the chance a child lineup gets a random move
+    mutation_rate: f64,
+    /// how to print the best lineup
+    format: Format,
+    /// file to write the running value after each iteration to,
+    /// or the best value after each generation
+    scores: Option<String>,
+    /// also solve exactly and report the gap to the annealing result
+    certify: bool,
+    /// stop the exact search after this many nodes
+    node_limit: Option<u64>,
+}
+
+impl Command {
+    fn parse() -> Result<Command, String> {
+        let mut args = std::env::args().skip(1);
+        let command = args.next().ok_or("missing command")?;
+        match command.as_str() {
+            "solve" => SolveArgs::parse(args).map(Command::Solve),
+            "validate" | "explain" => {
+                let (festival, lineup) = match (args.next(), args.next(), args.next()) {
+                    (Some(festival), Some(lineup), None) => (festival, lineup),
+                    _ => {
+                        return Err(format!(
+                            "{} takes a festival file and a lineup file",
+                            command
+                        ))
                     }
-                })
-            }
-        };
-        let babes_and_bullets = {
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Babes and Bullets")
-                .unwrap()
-                .clone()
-                .1;
-            !self.any_same_stage(
-                &loc,
-                vec!["Rico’s Revenge", "Robert Miyagi", "DJ Swedissh Cheff"],
-            )
-        };
-        let infusion = {
-            // must be playing back to back
-            let first_pos = self
-                .knapsack
-                .iter()
-                .position(|(band, _)| &band.name == "Infu$ion")
-                .unwrap();
-            let first_loc = self.knapsack[first_pos].1.clone();
-            let second_loc = self
-                .knapsack
-                .iter()
-                .skip(first_pos)
-                .find(|(band, _)| &band.name == "Infu$ion")
-                .unwrap()
-                .clone()
-                .1;
-            if first_loc == second_loc && first_loc != Location::Unused {
-                let schedule = match first_loc {
-                    Location::Amphitheater => &self.amphitheater.schedule,
-                    Location::Stadium => &self.stadium.schedule,
-                    Location::Tent => &self.tent.schedule,
-                    Location::Unused => panic!("unused"),
                 };
-                let first = schedule
-                    .iter()
-                    .position(|slot| {
-                        if let TimeSlot::BandSlot(_, band) = slot {
-                            &band.name == "Infu$ion"
-                        } else {
-                            false
-                        }
-                    })
-                    .unwrap();
-                let second = schedule
-                    .iter()
-                    .position(|slot| {
-                        if let TimeSlot::BandSlot(_, band) = slot {
-                            &band.name == "Infu$ion"
-                        } else {
-                            false
-                        }
-                    })
-                    .unwrap();
-                if first > second {
-                    first - second == 1
+                Ok(if command == "validate" {
+                    Command::Validate { festival, lineup }
                 } else {
-                    second - first == 1
-                }
-            } else {
-                true
+                    Command::Explain { festival, lineup }
+                })
             }
-        };
-        let hummingbird_anthem = {
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Hummingbird Anthem")
-                .unwrap()
-                .clone()
-                .1;
-            if let Some(TimeSlot::BandSlot(start_time, _)) =
-                self.get_schedule(&loc).iter().find(|slot| {
-                    if let TimeSlot::BandSlot(_, band) = slot {
-                        &band.name == "Hummingbird Anthem"
+            "export" => match (args.next(), args.next()) {
+                (Some(festival), None) => Ok(Command::Export { festival }),
+                _ => Err("export takes a festival file".to_string()),
+            },
+            "import" => {
+                let mut files = Vec::new();
+                let mut format = Format::Text;
+                while let Some(arg) = args.next() {
+                    if arg == "--format" {
+                        format = args.next().ok_or("--format needs a value")?.parse()?;
+                    } else if arg.starts_with("--") {
+                        return Err(format!("unknown option {}", arg));
                     } else {
-                        false
+                        files.push(arg);
                     }
-                })
-            {
-                // must play before 9 pm if playing
-                !time_is_after(9 * 60, *start_time, loc)
-            } else {
-                // otherwise true
-                true
-            }
-        };
-        let macaulay_and_co = {
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Macaulay & Co.")
-                .unwrap()
-                .clone()
-                .1;
-            if loc == Location::Unused {
-                true
-            } else {
-                let schedule = match loc {
-                    Location::Amphitheater => &self.amphitheater.schedule,
-                    Location::Stadium => &self.stadium.schedule,
-                    Location::Tent => &self.tent.schedule,
-                    Location::Unused => panic!("unused"),
-                };
-                if let Some(TimeSlot::BandSlot(_, band)) = schedule.last() {
-                    &band.name == "Macaulay & Co."
-                } else {
-                    false
+                }
+                match files.as_slice() {
+                    [festival, solution] => Ok(Command::Import {
+                        festival: festival.clone(),
+                        solution: solution.clone(),
+                        format,
+                    }),
+                    _ => Err("import takes a festival file and a solution file".to_string()),
                 }
             }
-        };
+            _ => Err(format!("unknown command {}", command)),
+        }
+    }
+}
 
-        let dj_megara = {
-            // cannot have the other dj before or after
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "DJ Megara")
-                .unwrap()
-                .clone()
-                .1;
-            if loc == Location::Unused {
-                true
-            } else {
-                let schedule = self.get_schedule(&loc);
-                let pos = schedule
-                    .iter()
-                    .position(|slot| {
-                        if let TimeSlot::BandSlot(_, band) = slot {
-                            &band.name == "DJ Megara"
-                        } else {
-                            false
-                        }
-                    })
-                    .unwrap();
-                match (
-                    schedule.get(pos.checked_sub(1).unwrap_or(usize::MAX)),
-                    schedule.get(pos + 1),
-                ) {
-                    (Some(TimeSlot::BandSlot(_, band)), _) if band.name == "DJ Swedissh Cheff" => {
-                        false
-                    }
-                    (_, Some(TimeSlot::BandSlot(_, band))) if band.name == "DJ Swedissh Cheff" => {
-                        false
-                    }
-                    _ => true,
+impl SolveArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<SolveArgs, String> {
+        let mut festival = None;
+        let mut solve = SolveArgs {
+            festival: String::new(),
+            roster: None,
+            budget: None,
+            iterations: 1_000,
+            seed: None,
+            solver: SolverKind::Annealing,
+            schedule: ScheduleKind::Geometric,
+            temperature: None,
+            chains: 4,
+            swap_interval: 100,
+            tenure: 10,
+            candidates: 20,
+            generations: 100,
+            population: 50,
+            mutation_rate: 0.3,
+            format: Format::Text,
+            scores: None,
+            certify: false,
+            node_limit: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--roster" => solve.roster = Some(value()?),
+                "--budget" => {
+                    let value = value()?;
+                    solve.budget = Some(
+                        value
+                            .replace('_', "")
+                            .parse()
+                            .map_err(|_| format!("invalid budget {}", value))?,
+                    );
+                }
+                "--iterations" => {
+                    let value = value()?;
+                    solve.iterations = value
+                        .parse()
+                        .map_err(|_| format!("invalid iteration count {}", value))?;
+                }
+                "--seed" => {
+                    let value = value()?;
+                    solve.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid seed {}", value))?,
+                    );
+                }
+                "--solver" => solve.solver = value()?.parse()?,
+                "--schedule" => solve.schedule = value()?.parse()?,
+                "--chains" => {
+                    let value = value()?;
+                    solve.chains =
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|chains| *chains > 1)
+                            .ok_or_else(|| {
+                                format!(
+                                    "invalid chain count {}, parallel tempering needs at least 2",
+                                    value
+                                )
+                            })?;
+                }
+                "--swap-interval" => {
+                    let value = value()?;
+                    solve.swap_interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("invalid swap interval {}", value))?;
+                }
+                "--tenure" => {
+                    let value = value()?;
+                    solve.tenure = value
+                        .parse()
+                        .map_err(|_| format!("invalid tenure {}", value))?;
+                }
+                "--candidates" => {
+                    let value = value()?;
+                    solve.candidates = value
+                        .parse()
+                        .ok()
+                        .filter(|candidates| *candidates > 0)
+                        .ok_or_else(|| format!("invalid candidate count {}", value))?;
+                }
+                "--generations" => {
+                    let value = value()?;
+                    solve.generations = value
+                        .parse()
+                        .map_err(|_| format!("invalid generation count {}", value))?;
+                }
+                "--population" => {
+                    let value = value()?;
+                    solve.population = value
+                        .parse()
+                        .ok()
+                        .filter(|population| *population >= 2)
+                        .ok_or_else(|| format!("invalid population {}", value))?;
+                }
+                "--mutation-rate" => {
+                    let value = value()?;
+                    solve.mutation_rate = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|rate| (0.0..=1.0).contains(rate))
+                        .ok_or_else(|| format!("invalid mutation rate {}", value))?;
+                }
+                "--temperature" => {
+                    let value = value()?;
+                    solve.temperature = Some(
+                        value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|temperature| *temperature > 0.0)
+                            .ok_or_else(|| format!("invalid temperature {}", value))?,
+                    );
+                }
+                "--format" => solve.format = value()?.parse()?,
+                "--scores" => solve.scores = Some(value()?),
+                "--certify" => solve.certify = true,
+                "--node-limit" => {
+                    let value = value()?;
+                    solve.node_limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid node limit {}", value))?,
+                    );
                 }
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ if festival.is_none() => festival = Some(arg),
+                _ => return Err(format!("unexpected argument {}", arg)),
             }
-        };
+        }
+        solve.festival = festival.ok_or("missing festival file")?;
+        Ok(solve)
+    }
+}
 
-        let fractured_coccyx = {
-            // cannot play in the tent
-            let loc = self
-                .knapsack
-                .iter()
-                .find(|(band, _)| &band.name == "Fractured Coccyx")
-                .unwrap()
-                .clone()
-                .1;
-            loc != Location::Tent
-        };
+/// Write a vector of scores to a file
+fn write_scores(scores: &[Money], filename: &str) -> io::Result<()> {
+    let data = scores
+        .iter()
+        .map(|score| score.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+    let mut f = File::create(filename)?;
+    f.write_all(data.as_bytes())
+}
+
+/// print a message and exit with `code`
+fn fail(code: i32, message: impl Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(code);
+}
+
+/// report an unreadable or malformed input file and exit
+fn invalid_input(path: impl Display, err: impl Display) -> ! {
+    fail(EXIT_INVALID_INPUT, format!("{}: {}", path, err))
+}
 
-        macy_dynamite
-            && illiterate_monkeys
-            && onyx_eyes
-            && babes_and_bullets
-            && infusion
-            && hummingbird_anthem
-            && macaulay_and_co
-            && dj_megara
-            && fractured_coccyx
+/// report a bad festival file; a rules file error already names the rules file
+fn invalid_config(path: &str, err: ConfigError) -> ! {
+    match err {
+        ConfigError::Rules(..) => fail(EXIT_INVALID_INPUT, err.to_string()),
+        err => invalid_input(path, err),
     }
-    fn value(&self) -> u32 {
-        self.amphitheater.value()
-            + self.stadium.value()
-            + self.tent.value()
-            + self.special_bonuses()
+}
+
+/// load a festival, applying any overrides from the command line
+fn load_festival(path: &str, roster: Option<&str>, budget: Option<Money>) -> Econochella {
+    let mut config = FestivalConfig::load(path).unwrap_or_else(|err| invalid_config(path, err));
+    if let Some(roster) = roster {
+        config.roster = roster.into();
     }
-    fn special_bonuses(&self) -> u32 {
-        0
+    if let Some(budget) = budget {
+        config.budget = budget;
     }
-    fn get_schedule(&self, loc: &Location) -> Vec<TimeSlot> {
-        match loc {
-            Location::Tent => self.tent.schedule.clone(),
-            Location::Amphitheater => self.amphitheater.schedule.clone(),
-            Location::Stadium => self.stadium.schedule.clone(),
-            Location::Unused => Vec::new(),
-        }
+    let bands = roster::load_bands(&config.roster)
+        .unwrap_or_else(|err| invalid_input(config.roster.display(), err));
+    Econochella::from_config(&config, bands).unwrap_or_else(|err| invalid_config(path, err))
+}
+
+/// load a festival and book a lineup file into it
+fn load_lineup(festival: &str, lineup: &str) -> Econochella {
+    let festival = load_festival(festival, None, None);
+    Lineup::load(lineup)
+        .and_then(|parsed| parsed.apply(&festival))
+        .unwrap_or_else(|err| invalid_input(lineup, err))
+}
+
+/// print each violation, returning the exit code for them
+fn report_violations(violations: &[Violation]) -> i32 {
+    if violations.is_empty() {
+        return EXIT_OK;
     }
-    /// returns true if any band from other names is playing in this location
-    fn any_same_stage(&self, loc: &Location, other_names: Vec<&str>) -> bool {
-        let schedule = self.get_schedule(loc);
-        // check for each name if the scheule contains this band
-        other_names.iter().any(|other_name| {
-            schedule.iter().any(|a| {
-                if let TimeSlot::BandSlot(_, band) = a {
-                    &band.name == other_name
-                } else {
-                    false
-                }
-            })
-        })
+    println!("Violations:");
+    for violation in violations {
+        println!("    {}", violation);
     }
+    EXIT_INFEASIBLE
 }
 
-#[derive(Clone)]
-struct Venue {
-    /// the venue's name
-    name: String,
-    /// The schedule, consisting of time slots of either a band or a break.
-    schedule: Vec<TimeSlot>,
-    /// Current time from start in minutes
-    current_time: u32,
-    /// Total time from start in minutes
-    total_time: u32,
-    /// Standard break time in minutes for this venue
-    break_time: u32,
-}
+/// anneal and print the best lineup found
+fn solve(args: SolveArgs) -> i32 {
+    let initial = load_festival(&args.festival, args.roster.as_deref(), args.budget);
+    // the searches only keep valid lineups, so they need a valid one to start from
+    let violations = initial.validate();
+    if !violations.is_empty() {
+        println!("The empty lineup is infeasible");
+        return report_violations(&violations);
+    }
 
-impl Venue {
-    fn new(name: String, break_time: u32, total_time: u32) -> Venue {
-        Venue {
-            name,
-            schedule: Vec::new(),
-            current_time: 0,
-            total_time,
-            break_time,
+    // pick a fresh seed unless one was given, so that every run can be replayed
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut generations = Vec::new();
+    let mut swap_rates = Vec::new();
+    let (solver, result) = match args.solver {
+        SolverKind::Annealing => {
+            let initial_temperature = args.temperature.unwrap_or_else(|| {
+                calibrate_temperature(&initial, CALIBRATION_SAMPLES, 0.8, &mut rng)
+            });
+            let annealing = SimulatedAnnealing {
+                iterations: args.iterations,
+                schedule: args.schedule,
+                initial_temperature,
+            };
+            (
+                format!(
+                    "annealing on a {} schedule from temperature {:.1}",
+                    args.schedule, initial_temperature
+                ),
+                annealing.solve(&initial, &mut rng),
+            )
         }
-    }
-    /// remove the last band with this name from the schedule
-    fn remove_band(&mut self, band: &Band) {
-        // find the last band with this name
-        if let Some(index) = self.schedule.iter().rposition(|time_slot| {
-            if let TimeSlot::BandSlot(_, b) = time_slot {
-                if b.name == band.name {
-                    return true;
-                }
+        SolverKind::Tabu => {
+            let tabu = TabuSearch {
+                iterations: args.iterations,
+                candidates: args.candidates,
+                tenure: args.tenure,
             };
-            false
-        }) {
-            if self.schedule.len() == 1 {
-                self.schedule.clear();
-                return;
-            }
-            if index == 0 {
-                // remove first two
-                self.schedule.drain(0..2);
-                return;
-            }
-            // remove and keep the value of the band
-            let slot = self.schedule.drain(index - 1..=index).next().unwrap();
-            // shift the remaining band's times
-            if let TimeSlot::BandSlot(start_time, band) = slot {
-                self.current_time -= band.time;
-                self.current_time -= self.break_time;
-                if let Some(TimeSlot::BandSlot(next_start_time, _)) = self.schedule.get(index) {
-                    let shift = next_start_time - start_time;
-                    self.schedule.iter_mut().skip(index).for_each(|time_slot| {
-                        if let TimeSlot::BandSlot(time, _) = time_slot {
-                            *time -= shift;
-                        }
-                    });
-                }
-            }
+            (
+                format!(
+                    "tabu search with a tenure of {} and {} candidates per iteration",
+                    args.tenure, args.candidates
+                ),
+                tabu.solve(&initial, &mut rng),
+            )
         }
-    }
-    /// add a band to the schedule in a random place
-    fn add_band(&mut self, band: Band, rng: &mut ThreadRng) {
-        if self.schedule.is_empty() {
-            self.current_time += band.time;
-            self.schedule.push(TimeSlot::BandSlot(0, band));
-            return;
+        SolverKind::Genetic => {
+            let genetic = GeneticAlgorithm {
+                generations: args.generations,
+                population: args.population,
+                mutation_rate: args.mutation_rate,
+            };
+            let Evolution {
+                result,
+                generations: statistics,
+            } = genetic.solve(&initial, &mut rng);
+            generations = statistics;
+            (
+                format!(
+                    "genetic algorithm with {} lineups over {} generations and a mutation rate of {}",
+                    args.population, args.generations, args.mutation_rate
+                ),
+                result,
+            )
         }
-        // insert a break, then increment the current time
-        let index = rng.gen_range(0..=(self.schedule.len() + 1) / 2) * 2;
-        if index == self.schedule.len() + 1 {
-            // insert a break after the last band and then the band
-            self.schedule.push(TimeSlot::Break);
-            self.current_time += self.break_time;
-            let temp_time = band.time;
-            self.schedule
-                .push(TimeSlot::BandSlot(self.current_time, band));
-            self.current_time += temp_time;
-        } else {
-            // insert band then a break after it
-            let time = if let TimeSlot::BandSlot(t, _) = self.schedule[index] {
-                t
-            } else {
-                panic!("expected band slot");
+        SolverKind::Tempering => {
+            let max_temperature = args.temperature.unwrap_or_else(|| {
+                calibrate_temperature(&initial, CALIBRATION_SAMPLES, 0.8, &mut rng)
+            });
+            let tempering = ParallelTempering {
+                iterations: args.iterations,
+                chains: args.chains,
+                swap_interval: args.swap_interval,
+                min_temperature: max_temperature / 100.0,
+                max_temperature,
             };
-            let temp_time = band.time;
-            self.schedule.insert(index, TimeSlot::BandSlot(time, band));
-            self.current_time += temp_time;
-            self.schedule.insert(index + 1, TimeSlot::Break);
-            self.current_time += self.break_time;
+            let Exchange {
+                result,
+                temperatures,
+                swap_rates: rates,
+            } = tempering.solve(&initial, &mut rng);
+            swap_rates = temperatures
+                .windows(2)
+                .zip(rates)
+                .map(|(pair, rate)| (pair[0], pair[1], rate))
+                .collect();
+            (
+                format!(
+                    "parallel tempering with {} chains from temperature {:.1} to {:.1}, swapping every {} iterations",
+                    args.chains, tempering.min_temperature, max_temperature, args.swap_interval
+                ),
+                result,
+            )
+        }
+    };
+    let SearchResult {
+        best: best_econochella,
+        scores,
+        rejections,
+    } = result;
+    if let Some(path) = &args.scores {
+        if let Err(err) = write_scores(&scores, path) {
+            fail(
+                EXIT_INTERNAL,
+                format!("{}: unable to write scores: {}", path, err),
+            );
         }
     }
-    /// find the total cost of the schedule
-    fn cost(&self) -> u32 {
-        self.schedule
-            .iter()
-            .fold(0, |acc, time_slot| match time_slot {
-                TimeSlot::BandSlot(_, band) => acc + band.cost,
-                TimeSlot::Break => acc,
-            })
+    let bound = upper_bound(&initial);
+    if args.format == Format::Lineup {
+        println!("# seed {}", seed);
+        match bound {
+            Some(bound) => println!(
+                "# value {} of an upper bound of {}",
+                best_econochella.value(),
+                bound
+            ),
+            None => println!("# value {}", best_econochella.value()),
+        }
+        print!("{}", Lineup::of(&best_econochella).to_toml());
+        if args.certify {
+            // as comments, so that the output stays a lineup file
+            certify(&initial, best_econochella.value(), args.node_limit, "# ");
+        }
+        return EXIT_OK;
     }
-    /// find the total time of the schedule
-    fn time(&self) -> u32 {
-        self.schedule
+    println!("Seed: {}", seed);
+    println!("Solver: {}", solver);
+    println!(
+        "The cost is {}, the value is {}, and the times are {}",
+        best_econochella.cost(),
+        best_econochella.value(),
+        best_econochella
+            .venues()
             .iter()
-            .fold(0, |acc, time_slot| match time_slot {
-                TimeSlot::BandSlot(_, band) => acc + band.time,
-                TimeSlot::Break => acc + self.break_time,
-            })
+            .map(|venue| format!("{}: {}", venue.label(), venue.time()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    match bound {
+        Some(bound) => println!(
+            "Upper bound: {}, the value is within {:.2}%",
+            bound,
+            100.0 * (bound - best_econochella.value()) as f64 / bound.abs().max(1) as f64
+        ),
+        None => println!("Upper bound: none, a bonus gives no bound"),
     }
-
-    /// find the value of the schedule
-    fn value(&self) -> u32 {
-        self.schedule
-            .iter()
-            .fold(0, |acc, time_slot| match time_slot {
-                TimeSlot::BandSlot(_, band) => acc + band.value(),
-                TimeSlot::Break => acc,
-            })
+    if !swap_rates.is_empty() {
+        println!("Accepted swaps by temperature:");
+        for (cold, hot, rate) in swap_rates {
+            println!("    {:.1} and {:.1}: {:.1}%", cold, hot, 100.0 * rate);
+        }
     }
-}
-
-impl fmt::Display for Venue {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{}", self.name)?;
-        writeln!(f, "    Time used: {}/{}", self.time(), self.total_time)?;
-        writeln!(f, "    Cost: {}", self.cost())?;
-        writeln!(f, "    Value: {}", self.value())?;
-        for el in &self.schedule {
-            match el {
-                TimeSlot::BandSlot(_, band) => {
-                    writeln!(f, "    {} for {} minutes", band.name, band.time)?
-                }
-                TimeSlot::Break => writeln!(f, "    Break for {} minutes", self.break_time)?,
-            };
+    if !generations.is_empty() {
+        println!("Best and mean value by generation:");
+        for (number, generation) in generations.iter().enumerate() {
+            println!("    {}: {} {:.0}", number, generation.best, generation.mean);
         }
-        Ok(())
     }
+    for venue in best_econochella.venues() {
+        println!("{}", venue);
+    }
+    println!("Bonuses:");
+    for (bonus, value) in best_econochella.bonus_breakdown() {
+        println!("    {}: {}", bonus, value);
+    }
+    let mut rejections: Vec<(String, u32)> = rejections.into_iter().collect();
+    rejections.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if args.solver == SolverKind::Genetic {
+        println!("Repaired children by check:");
+    } else {
+        println!("Rejected candidates by check:");
+    }
+    for (label, count) in rejections {
+        println!("    {}: {}", count, label);
+    }
+    if args.certify {
+        certify(&initial, best_econochella.value(), args.node_limit, "");
+    }
+    EXIT_OK
 }
 
-#[derive(Clone, PartialEq)]
-enum Location {
-    Tent,
-    Amphitheater,
-    Stadium,
-    Unused,
-}
-
-impl Location {
-    fn choose_location(&self, rng: &mut ThreadRng) -> Location {
-        match rng.gen_range(0..4) {
-            0 => Location::Tent,
-            1 => Location::Amphitheater,
-            2 => Location::Stadium,
-            3 => Location::Unused,
-            _ => panic!("invalid location"),
+/// solve exactly and print the gap between the optimum and a heuristic's value,
+/// starting each line with `prefix`
+fn certify(initial: &Econochella, heuristic: Money, node_limit: Option<u64>, prefix: &str) {
+    let say = |text: String| {
+        for line in text.lines() {
+            println!("{}{}", prefix, line);
+        }
+    };
+    let exact = BranchAndBound { node_limit }.solve(initial);
+    let found = exact.best.as_ref().map(Econochella::value);
+    if !exact.proven {
+        match found {
+            Some(value) => say(format!(
+                "Best exact lineup: {} after {} nodes, stopped before proving it optimal",
+                value, exact.nodes
+            )),
+            None => say(format!(
+                "No exact lineup after {} nodes, stopped before finding one",
+                exact.nodes
+            )),
         }
+        return;
     }
+    // the heuristic's lineup is valid, so a complete search finds one at least as good
+    let optimum = match (&exact.best, found) {
+        (Some(best), Some(optimum)) if optimum >= heuristic => {
+            if optimum > heuristic {
+                say("Exact lineup:".to_string());
+                for venue in best.venues() {
+                    say(venue.to_string());
+                }
+            }
+            optimum
+        }
+        _ => fail(
+            EXIT_INTERNAL,
+            format!(
+                "the heuristic's value {} beats the exact optimum {}",
+                heuristic,
+                found.map_or("of no lineup".to_string(), |value| value.to_string())
+            ),
+        ),
+    };
+    say(format!(
+        "Exact optimum: {} after {} nodes",
+        optimum, exact.nodes
+    ));
+    say(format!(
+        "Optimality gap: {} ({:.2}%)",
+        optimum - heuristic,
+        100.0 * (optimum - heuristic) as f64 / optimum.abs().max(1) as f64
+    ));
 }
 
-#[derive(Clone, Debug)]
-enum TimeSlot {
-    Break,
-    /// (time of starting, band)
-    BandSlot(u32, Band),
-}
-
-#[derive(Clone, Debug)]
-struct Band {
-    /// Name of band
-    name: String,
-    /// Time to play in minutes
-    time: u32,
-    /// Anticipated revenue in dollars
-    /// (if this were not accounted for, we would not have econochella at all. we would simply pocket the budget)
-    revenue: u32,
-    /// Cost in dollars
-    cost: u32,
-}
-
-impl Band {
-    /// find the value of the band
-    fn value(&self) -> u32 {
-        self.revenue - self.cost
+/// check a lineup, printing its totals if valid and its violations if not
+fn validate(festival: &str, lineup: &str) -> i32 {
+    let booked = load_lineup(festival, lineup);
+    let violations = booked.validate();
+    if violations.is_empty() {
+        println!(
+            "Valid: the cost is {} of a budget of {}, and the value is {}",
+            booked.cost(),
+            booked.budget(),
+            booked.value()
+        );
     }
+    report_violations(&violations)
 }
 
-/// Expected time ought to be the minutes since noon
-fn time_is_after(expected_time: u32, time_since_start: u32, loc: Location) -> bool {
-    let start_time = match loc {
-        Location::Tent => 300,
-        Location::Amphitheater => 240,
-        Location::Stadium => 360,
-        Location::Unused => u32::MIN,
-    };
-    start_time + time_since_start > expected_time
+/// print the value of every set, venue and bonus in a lineup, and its violations
+fn explain(festival: &str, lineup: &str) -> i32 {
+    let booked = load_lineup(festival, lineup);
+    for venue in booked.venues() {
+        println!(
+            "{} ({}\u{2013}{}): cost {}, value {}",
+            venue.label(),
+            venue.opening_time(),
+            venue.curfew(),
+            venue.cost(),
+            venue.value()
+        );
+        for time_slot in venue.schedule() {
+            if let TimeSlot::BandSlot(start_time, band) = time_slot {
+                let value = band.value_at(*start_time);
+                println!(
+                    "    {}\u{2013}{} {}: revenue {}, fee {}, value {}",
+                    start_time,
+                    *start_time + band.time,
+                    band.label(),
+                    value + band.cost,
+                    band.cost,
+                    value
+                );
+            }
+        }
+    }
+    println!("Bonuses: {}", booked.special_bonuses());
+    for (bonus, value) in booked.bonus_breakdown() {
+        println!("    {}: {}", bonus, value);
+    }
+    println!(
+        "Total: cost {} of a budget of {}, value {}",
+        booked.cost(),
+        booked.budget(),
+        booked.value()
+    );
+    report_violations(&booked.validate())
 }
 
-/// Write a vector of scores to a file
-fn write_scores(scores: &[u32], filename: &str) {
-    let data = scores
-        .iter()
-        .map(|score| score.to_string())
-        .collect::<Vec<String>>()
-        .join(",");
-    let mut f = File::create(filename).expect("Unable to create file");
-    f.write_all(data.as_bytes()).expect("Unable to write data");
+/// book a solver's solution and check it, printing it as a report or a lineup file
+fn import(festival: &str, solution: &str, format: Format) -> i32 {
+    let initial = load_festival(festival, None, None);
+    let booked =
+        load_solution(&initial, solution).unwrap_or_else(|err| invalid_input(solution, err));
+    let violations = booked.validate();
+    if format == Format::Lineup {
+        print!("{}", Lineup::of(&booked).to_toml());
+        for violation in &violations {
+            eprintln!("{}", violation);
+        }
+        return if violations.is_empty() {
+            EXIT_OK
+        } else {
+            EXIT_INFEASIBLE
+        };
+    }
+    for venue in booked.venues() {
+        println!("{}", venue);
+    }
+    if violations.is_empty() {
+        println!(
+            "Valid: the cost is {} of a budget of {}, and the value is {}",
+            booked.cost(),
+            booked.budget(),
+            booked.value()
+        );
+    }
+    report_violations(&violations)
 }
 
-/// Initialize the Econochella, Venues, and Bands
-/// Econochella has the best seen knapsack, we operate on a running subset
-/// For the number of iterations
-///     Create a temporary knapsack / Econochella
-///     Choose a band in the temporary knapsack / Econochella
-///     Randomly choose a different state (none or location) and change the band's state
-///     If this is feasible // ! run the checking function
-///         delta = the score of the temporary knapsack / Econochella - the score of the running Econochella
-///         If the delta is positive OR if a randum number between 0 and 1 < e ^ (delta / Temperature)
-///             update the running best knapsack in Econochella
-///         If the score of the running knapsack is better than the score of the best knapsack / Econochella
-///             update the best knapsack in Econochella
 fn main() {
-    let knapsack: Vec<Band> = vec![
-        Band {
-            name: "The Bionic Men".to_string(),
-            time: 60,
-            revenue: 300_000,
-            cost: 100_000,
-        },
-        Band {
-            name: "Les Salter and the Ignition".to_string(),
-            time: 80,
-            revenue: 300_000,
-            cost: 95_000,
-        },
-        Band {
-            name: "Macy Dynamite".to_string(),
-            time: 60,
-            revenue: 900_000,
-            cost: 270_000,
-        },
-        Band {
-            name: "Illiterate Monkeys".to_string(),
-            time: 60,
-            revenue: 200_000,
-            cost: 75_000,
-        },
-        Band {
-            name: "Chicken Fried Awesome".to_string(),
-            time: 40,
-            revenue: 75_000,
-            cost: 25_000,
-        },
-        Band {
-            name: "Babes and Bullets".to_string(),
-            time: 40,
-            revenue: 200_000,
-            cost: 40_000,
-        },
-        Band {
-            name: "Infu$ion".to_string(),
-            time: 50,
-            revenue: 100_000,
-            cost: 65_000,
-        },
-        Band {
-            name: "Infu$ion".to_string(),
-            time: 50,
-            revenue: 100_000,
-            cost: 65_000,
-        },
-        Band {
-            name: "Rico’s Revenge".to_string(),
-            time: 70,
-            revenue: 500_000,
-            cost: 300_000,
-        },
-        Band {
-            name: "The Potato Head Project".to_string(),
-            time: 30,
-            revenue: 200_000,
-            cost: 18_000,
-        },
-        Band {
-            name: "Robert Miyagi".to_string(),
-            time: 90,
-            revenue: 600_000,
-            cost: 340_000,
-        },
-        Band {
-            name: "Aluminum Falcon".to_string(),
-            time: 60,
-            revenue: 300_000,
-            cost: 45_000,
-        },
-        Band {
-            name: "DJ Swedissh Cheff".to_string(),
-            time: 60,
-            revenue: 200_000,
-            cost: 70_000,
-        },
-        Band {
-            name: "Caffeine Buzz".to_string(),
-            time: 70,
-            revenue: 100_000,
-            cost: 45_000,
-        },
-        Band {
-            name: "Caffeine Buzz".to_string(),
-            time: 70,
-            revenue: 100_000,
-            cost: 45_000,
-        },
-        Band {
-            name: "647 Buckingham Way".to_string(),
-            time: 100,
-            revenue: 600_000,
-            cost: 80_000,
-        },
-        Band {
-            name: "Hummingbird Anthem".to_string(),
-            time: 60,
-            revenue: 400_000,
-            cost: 60_000,
-        },
-        Band {
-            name: "Macaulay & Co.".to_string(),
-            time: 80,
-            revenue: 300_000,
-            cost: 220_000,
-        },
-        Band {
-            name: "Sonderbund".to_string(),
-            time: 70,
-            revenue: 600_000,
-            cost: 120_000,
-        },
-        Band {
-            name: "Onyx Eyes".to_string(),
-            time: 90,
-            revenue: 800_000,
-            cost: 190_000,
-        },
-        Band {
-            name: "DJ Megara".to_string(),
-            time: 60,
-            revenue: 250_000,
-            cost: 50_000,
-        },
-        Band {
-            name: "Sloth Central Incorporated".to_string(),
-            time: 50,
-            revenue: 150_000,
-            cost: 45_000,
-        },
-        Band {
-            name: "Sloth Central Incorporated".to_string(),
-            time: 50,
-            revenue: 150_000,
-            cost: 45_000,
-        },
-        Band {
-            name: "Fractured Coccyx".to_string(),
-            time: 90,
-            revenue: 400_000,
-            cost: 200_000,
-        },
-        Band {
-            name: "Forgotten Indigo".to_string(),
-            time: 30,
-            revenue: 50_000,
-            cost: 0,
-        },
-    ];
-    let mut best_econochella = Econochella::new(1_370_000, 0.5, knapsack);
-    let mut running_econochella = best_econochella.clone();
-
-    let mut rng = rand::thread_rng();
-    let mut scores = Vec::new();
-
-    for _ in 0..1_000 {
-        let mut temp_econochella = best_econochella.clone();
-        let (band, current_location) = temp_econochella.choose_band(&mut rng);
-        // choose a random location. choosing the same location amounts to a deletion and a random reinsertion
-        let new_location = current_location.choose_location(&mut rng);
-        // move the band to a random time in the new location's schedule
-        temp_econochella.move_band(band, current_location, new_location, &mut rng);
-        if temp_econochella.valid() {
-            let delta = temp_econochella.value() as f64 - running_econochella.value() as f64;
-            if delta > 0.0 || rng.gen::<f64>() < (-delta / TEMPERATURE).exp() {
-                running_econochella = temp_econochella;
-            }
-            if running_econochella.value() > best_econochella.value() {
-                best_econochella = running_econochella.clone();
-            }
+    let command = Command::parse().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        fail(EXIT_USAGE, USAGE)
+    });
+    process::exit(match command {
+        Command::Solve(args) => solve(args),
+        Command::Validate { festival, lineup } => validate(&festival, &lineup),
+        Command::Explain { festival, lineup } => explain(&festival, &lineup),
+        Command::Export { festival } => {
+            print!("{}", export_lp(&load_festival(&festival, None, None)));
+            EXIT_OK
         }
-        scores.push(running_econochella.value());
-    }
-    write_scores(&scores, "./running_values.txt");
-    println!(
-        "The cost is {}, the value is {}, and the times are tent: {}, amphitheater: {}, stadium: {}",
-        best_econochella.tent.cost()
-        + best_econochella.amphitheater.cost()
-        + best_econochella.stadium.cost(),
-        best_econochella.value(),
-        best_econochella.tent.time(),
-        best_econochella.amphitheater.time(),
-        best_econochella.stadium.time()
-    );
-    println!(
-        "{}\n{}\n{}\n",
-        best_econochella.tent, best_econochella.amphitheater, best_econochella.stadium
-    );
+        Command::Import {
+            festival,
+            solution,
+            format,
+        } => import(&festival, &solution, format),
+    });
 }
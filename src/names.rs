@@ -0,0 +1,158 @@
+use core::fmt;
+use std::sync::Arc;
+
+/// A band's id, assigned in roster order when a festival is built and shared by all its sets
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BandId(pub(crate) usize);
+
+impl BandId {
+    /// the band's position among the roster's bands
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A band as a rule or bonus refers to it: its id, and its name for messages
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BandRef {
+    pub id: BandId,
+    pub name: Arc<str>,
+}
+
+impl fmt::Display for BandRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// A name that is not on the roster, with the closest name that is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownBand {
+    pub name: String,
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownBand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.suggestion {
+            Some(known) => write!(f, "unknown band {}; did you mean {}?", self.name, known),
+            None => write!(f, "unknown band {}", self.name),
+        }
+    }
+}
+
+/// The roster's band names, each stored once and indexed by `BandId`
+#[derive(Clone, Debug, Default)]
+pub struct BandNames {
+    names: Vec<Arc<str>>,
+}
+
+impl BandNames {
+    /// the id of the band with this name, adding it if it is new
+    pub fn intern(&mut self, name: &str) -> BandId {
+        if let Some(id) = self.id(name) {
+            return id;
+        }
+        self.names.push(Arc::from(name));
+        BandId(self.names.len() - 1)
+    }
+    /// the id of the band with exactly this name
+    pub fn id(&self, name: &str) -> Option<BandId> {
+        self.names
+            .iter()
+            .position(|known| &**known == name)
+            .map(BandId)
+    }
+    /// the shared copy of a band's name
+    pub fn name(&self, id: BandId) -> &Arc<str> {
+        &self.names[id.0]
+    }
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+    /// the band a festival file or lineup names, or an error suggesting the closest name
+    pub fn resolve(&self, name: &str) -> Result<BandRef, UnknownBand> {
+        match self.id(name) {
+            Some(id) => Ok(BandRef {
+                id,
+                name: self.names[id.0].clone(),
+            }),
+            None => Err(self.unknown(name)),
+        }
+    }
+    /// the error for a name not on the roster, with the closest known name if one is close enough
+    pub fn unknown(&self, name: &str) -> UnknownBand {
+        UnknownBand {
+            name: name.to_string(),
+            suggestion: self.suggest(name).map(str::to_string),
+        }
+    }
+    /// the known name fewest single-character edits from `name`, ignoring case,
+    /// if it is within a third of the name's length
+    pub fn suggest(&self, name: &str) -> Option<&str> {
+        let wanted = name.to_lowercase();
+        let limit = (wanted.chars().count() / 3).max(1);
+        self.names
+            .iter()
+            .map(|known| (edit_distance(&wanted, &known.to_lowercase()), known))
+            .filter(|(distance, _)| *distance <= limit)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, known)| &**known)
+    }
+}
+
+/// the Levenshtein distance between two strings, counting characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> BandNames {
+        let mut names = BandNames::default();
+        for name in ["Macaulay & Co.", "Macy Dynamite", "Onyx Eyes"] {
+            names.intern(name);
+        }
+        names
+    }
+
+    #[test]
+    fn interns_each_name_once() {
+        let mut names = names();
+        assert_eq!(names.intern("Macy Dynamite"), BandId(1));
+        assert_eq!(names.len(), 3);
+        assert_eq!(&**names.name(BandId(2)), "Onyx Eyes");
+        assert_eq!(names.resolve("Onyx Eyes").unwrap().id, BandId(2));
+    }
+
+    #[test]
+    fn suggests_the_closest_name() {
+        let names = names();
+        assert_eq!(names.suggest("Macauley & Co."), Some("Macaulay & Co."));
+        assert_eq!(names.suggest("onyx eyes"), Some("Onyx Eyes"));
+        assert_eq!(names.suggest("Sonderbund"), None);
+        assert_eq!(
+            names.resolve("Macy Dynamight").unwrap_err().to_string(),
+            "unknown band Macy Dynamight; did you mean Macy Dynamite?"
+        );
+        assert_eq!(
+            names.resolve("Sonderbund").unwrap_err().to_string(),
+            "unknown band Sonderbund"
+        );
+    }
+}
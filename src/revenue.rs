@@ -0,0 +1,143 @@
+use crate::clock::ClockTime;
+use crate::config::ConfigError;
+use serde::Deserialize;
+
+/// A band's revenue multiplier over the clock time its set starts
+/// Piecewise constant: each point applies from its time until the next point
+#[derive(Clone, Debug, Default)]
+pub struct RevenueCurve {
+    /// (start time, multiplier), sorted by time
+    points: Vec<(ClockTime, f64)>,
+}
+
+impl RevenueCurve {
+    pub fn new(mut points: Vec<(ClockTime, f64)>) -> RevenueCurve {
+        points.sort_by_key(|(time, _)| *time);
+        RevenueCurve { points }
+    }
+    /// the multiplier for a set starting at this time
+    /// 1 before the first point
+    pub fn multiplier(&self, time: ClockTime) -> f64 {
+        self.points
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= time)
+            .map_or(1.0, |(_, multiplier)| *multiplier)
+    }
+    /// the constant pieces of the curve as (from, until, multiplier), covering every start time
+    pub fn segments(&self) -> Vec<(ClockTime, Option<ClockTime>, f64)> {
+        let mut segments = Vec::new();
+        let mut from = ClockTime::NOON;
+        let mut multiplier = 1.0;
+        for &(time, next) in &self.points {
+            if time > from {
+                segments.push((from, Some(time), multiplier));
+            }
+            from = time;
+            multiplier = next;
+        }
+        segments.push((from, None, multiplier));
+        segments
+    }
+}
+
+/// A revenue curve as written in the festival file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevenueCurveConfig {
+    pub band: String,
+    pub multipliers: Vec<MultiplierConfig>,
+}
+
+/// The revenue multiplier for sets starting at or after a clock time
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MultiplierConfig {
+    /// clock time, e.g. "20:00"
+    pub from: String,
+    pub multiplier: f64,
+}
+
+impl RevenueCurveConfig {
+    pub fn build(&self) -> Result<RevenueCurve, ConfigError> {
+        let points = self
+            .multipliers
+            .iter()
+            .map(|point| {
+                if !(point.multiplier.is_finite() && point.multiplier >= 0.0) {
+                    return Err(ConfigError::Invalid(format!(
+                        "invalid revenue multiplier {} for {}, expected a finite number, 0 or more",
+                        point.multiplier, self.band
+                    )));
+                }
+                Ok((point.from.parse()?, point.multiplier))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(RevenueCurve::new(points))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> ClockTime {
+        text.parse().unwrap()
+    }
+
+    fn curve(multipliers: &[(&str, f64)]) -> Result<RevenueCurve, ConfigError> {
+        RevenueCurveConfig {
+            band: "Macy Dynamite".to_string(),
+            multipliers: multipliers
+                .iter()
+                .map(|&(from, multiplier)| MultiplierConfig {
+                    from: from.to_string(),
+                    multiplier,
+                })
+                .collect(),
+        }
+        .build()
+    }
+
+    #[test]
+    fn applies_each_multiplier_until_the_next() {
+        let curve = curve(&[("22:00", 0.5), ("18:00", 1.5)]).unwrap();
+        assert_eq!(curve.multiplier(time("17:59")), 1.0);
+        assert_eq!(curve.multiplier(time("18:00")), 1.5);
+        assert_eq!(curve.multiplier(time("21:59")), 1.5);
+        assert_eq!(curve.multiplier(time("22:00")), 0.5);
+        assert_eq!(curve.multiplier(time("01:00")), 0.5);
+        assert_eq!(RevenueCurve::default().multiplier(time("20:00")), 1.0);
+    }
+
+    #[test]
+    fn splits_the_day_into_segments() {
+        let curve = curve(&[("22:00", 0.5), ("18:00", 1.5)]).unwrap();
+        assert_eq!(
+            curve.segments(),
+            vec![
+                (ClockTime::NOON, Some(time("18:00")), 1.0),
+                (time("18:00"), Some(time("22:00")), 1.5),
+                (time("22:00"), None, 0.5),
+            ]
+        );
+        // a point at noon replaces the default multiplier rather than adding an empty segment
+        assert_eq!(
+            self::curve(&[("12:00", 2.0)]).unwrap().segments(),
+            vec![(ClockTime::NOON, None, 2.0)]
+        );
+        assert_eq!(
+            RevenueCurve::default().segments(),
+            vec![(ClockTime::NOON, None, 1.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_multipliers_that_are_not_finite_or_are_negative() {
+        for multiplier in [-0.5, f64::NAN, f64::INFINITY] {
+            assert!(curve(&[("20:00", multiplier)]).is_err(), "{}", multiplier);
+        }
+        assert!(curve(&[("20:00", 0.0)]).is_ok());
+        assert!(curve(&[("25:00", 1.0)]).is_err());
+    }
+}
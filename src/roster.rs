@@ -0,0 +1,293 @@
+use crate::{Band, Money};
+use core::fmt;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// An error encountered while reading a roster file
+#[derive(Debug)]
+pub enum RosterError {
+    /// the file could not be read
+    Io(io::Error),
+    /// a row could not be parsed, with the 1-based line number
+    Malformed { line: usize, message: String },
+    /// the file lists no bands
+    Empty,
+}
+
+impl fmt::Display for RosterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RosterError::Io(err) => write!(f, "unable to read roster: {}", err),
+            RosterError::Malformed { line, message } => {
+                write!(f, "roster line {}: {}", line, message)
+            }
+            RosterError::Empty => write!(f, "the roster lists no bands"),
+        }
+    }
+}
+
+impl From<io::Error> for RosterError {
+    fn from(err: io::Error) -> RosterError {
+        RosterError::Io(err)
+    }
+}
+
+/// the most sets a band may play, over all of its roster rows
+pub const MAX_SETS: usize = 100;
+
+/// Load the bands from a tab-separated roster file, one entry per set each band could play
+/// Each row is: name, description, set length ("60 minutes"), fee ("100,000"), revenue ("300,000"),
+/// and optionally the most sets the band will play ("2", by default one per fee or revenue listed)
+/// A band's fee and revenue may differ by set, listed in order such as "100,000 / 80,000",
+/// with the last applying to every set after it
+/// A band on several rows, as in rosters that list one row per set, plays the sets of each row in turn
+pub fn load_bands<P: AsRef<Path>>(path: P) -> Result<Vec<Band>, RosterError> {
+    parse_bands(&fs::read_to_string(path)?)
+}
+
+/// Parse the bands from the contents of a roster file, skipping blank lines,
+/// which must list at least one band
+pub fn parse_bands(contents: &str) -> Result<Vec<Band>, RosterError> {
+    let mut bands: Vec<Band> = Vec::new();
+    // the sets of each band on the rows so far
+    let mut sets: HashMap<Arc<str>, usize> = HashMap::new();
+    for (index, row) in contents.lines().enumerate() {
+        if row.trim().is_empty() {
+            continue;
+        }
+        let malformed = |message| RosterError::Malformed {
+            line: index + 1,
+            message,
+        };
+        let row = parse_row(row).map_err(malformed)?;
+        let total = sets.entry(row[0].name.clone()).or_default();
+        *total += row.len();
+        if *total > MAX_SETS {
+            return Err(malformed(format!(
+                "{} sets of {} is more than the {} a band may play",
+                total, row[0].name, MAX_SETS
+            )));
+        }
+        bands.extend(row);
+    }
+    if bands.is_empty() {
+        return Err(RosterError::Empty);
+    }
+    Ok(bands)
+}
+
+/// the band on a row, once per set
+fn parse_row(row: &str) -> Result<Vec<Band>, String> {
+    let columns: Vec<&str> = row.split('\t').map(str::trim).collect();
+    if columns.len() != 5 && columns.len() != 6 {
+        return Err(format!(
+            "expected 5 or 6 tab-separated columns (name, description, time, fee, revenue, \
+             and optionally sets), found {}",
+            columns.len()
+        ));
+    }
+    let name = columns[0];
+    if name.is_empty() {
+        return Err("missing band name".to_string());
+    }
+    let time = parse_minutes(columns[2])?;
+    let fees = parse_amounts(columns[3])?;
+    let revenues = parse_amounts(columns[4])?;
+    let listed = fees.len().max(revenues.len());
+    let sets = match columns.get(5) {
+        Some(field) => field
+            .parse()
+            .ok()
+            .filter(|&sets| sets > 0)
+            .ok_or_else(|| format!("invalid number of sets {:?}", field))?,
+        None => listed,
+    };
+    if sets > MAX_SETS {
+        return Err(format!(
+            "{} sets is more than the {} a band may play",
+            sets, MAX_SETS
+        ));
+    }
+    if sets < listed {
+        return Err(format!(
+            "{} amounts are listed for at most {} sets",
+            listed, sets
+        ));
+    }
+    // a band's later sets repeat the last amount listed
+    let nth = |amounts: &[Money], set: usize| amounts[set.min(amounts.len() - 1)];
+    Ok((0..sets)
+        .map(|set| Band {
+            name: name.into(),
+            time,
+            revenue: nth(&revenues, set),
+            cost: nth(&fees, set),
+            ..Band::default()
+        })
+        .collect())
+}
+
+/// parse a set length such as "60 minutes"
+fn parse_minutes(field: &str) -> Result<u32, String> {
+    field
+        .trim_end_matches("minutes")
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid set length {:?}", field))
+}
+
+/// parse one or more dollar amounts separated by "/", such as "100,000 / 80,000"
+fn parse_amounts(field: &str) -> Result<Vec<Money>, String> {
+    field.split('/').map(str::trim).map(parse_dollars).collect()
+}
+
+/// parse a dollar amount such as "100,000", allowing a leading "$"
+fn parse_dollars(field: &str) -> Result<Money, String> {
+    field
+        .trim_start_matches('$')
+        .replace(',', "")
+        .parse()
+        .map_err(|_| format!("invalid dollar amount {:?}", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the name, fee and revenue of each set in a roster
+    fn sets(contents: &str) -> Vec<(String, Money, Money)> {
+        parse_bands(contents)
+            .unwrap()
+            .into_iter()
+            .map(|band| (band.name.to_string(), band.cost, band.revenue))
+            .collect()
+    }
+
+    fn set(name: &str, cost: Money, revenue: Money) -> (String, Money, Money) {
+        (name.to_string(), cost, revenue)
+    }
+
+    /// the message of a roster's error
+    fn malformed(contents: &str) -> String {
+        match parse_bands(contents) {
+            Err(RosterError::Malformed { line: 1, message }) => message,
+            other => panic!("expected an error on line 1, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_the_number_of_sets() {
+        assert_eq!(
+            sets("Sonderbund\t\t60 minutes\t100,000\t300,000\t3"),
+            vec![
+                set("Sonderbund", 100_000, 300_000),
+                set("Sonderbund", 100_000, 300_000),
+                set("Sonderbund", 100_000, 300_000),
+            ]
+        );
+        assert_eq!(
+            malformed("Sonderbund\t\t60 minutes\t100,000\t300,000\t0"),
+            "invalid number of sets \"0\""
+        );
+        assert_eq!(
+            malformed("Sonderbund\t\t60 minutes\t100,000\t300,000\t100000000"),
+            "100000000 sets is more than the 100 a band may play"
+        );
+    }
+
+    #[test]
+    fn reads_amounts_per_set() {
+        assert_eq!(
+            sets("Sonderbund\t\t60 minutes\t100,000 / 80,000\t300,000\t3"),
+            vec![
+                set("Sonderbund", 100_000, 300_000),
+                set("Sonderbund", 80_000, 300_000),
+                set("Sonderbund", 80_000, 300_000),
+            ]
+        );
+        assert_eq!(
+            sets("Sonderbund\t\t60 minutes\t100,000\t300,000 / $200,000"),
+            vec![
+                set("Sonderbund", 100_000, 300_000),
+                set("Sonderbund", 100_000, 200_000),
+            ]
+        );
+        assert_eq!(
+            malformed("Sonderbund\t\t60 minutes\t100,000 / 80,000\t300,000\t1"),
+            "2 amounts are listed for at most 1 sets"
+        );
+        assert_eq!(
+            malformed("Sonderbund\t\t60 minutes\t100,000 / lots\t300,000"),
+            "invalid dollar amount \"lots\""
+        );
+    }
+
+    #[test]
+    fn reads_a_row_per_set() {
+        assert_eq!(
+            sets("Sonderbund\t\t60 minutes\t100,000\t300,000\nOnyx Eyes\t\t45 minutes\t20,000\t50,000\nSonderbund\t\t60 minutes\t80,000\t250,000"),
+            vec![
+                set("Sonderbund", 100_000, 300_000),
+                set("Onyx Eyes", 20_000, 50_000),
+                set("Sonderbund", 80_000, 250_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_the_sets_over_every_row() {
+        let row = "Sonderbund\t\t60 minutes\t100,000\t300,000\t60\n";
+        match parse_bands(&row.repeat(2)) {
+            Err(RosterError::Malformed { line, message }) => {
+                assert_eq!(line, 2);
+                assert_eq!(
+                    message,
+                    "120 sets of Sonderbund is more than the 100 a band may play"
+                );
+            }
+            other => panic!("expected too many sets, found {:?}", other),
+        }
+        assert_eq!(
+            parse_bands(&format!("{}Onyx Eyes\t\t45\t20,000\t50,000\t60", row))
+                .unwrap()
+                .len(),
+            120
+        );
+    }
+
+    #[test]
+    fn reads_a_row_per_band() {
+        let bands = parse_bands(
+            "Onyx Eyes\tpost-rock\t45 minutes\t$20,000\t50,000\n\n\
+             Sonderbund\tkrautrock\t60\t100,000\t300,000\n",
+        )
+        .unwrap();
+        assert_eq!(bands.len(), 2);
+        assert_eq!(&*bands[0].name, "Onyx Eyes");
+        assert_eq!(
+            (bands[0].time, bands[0].cost, bands[0].revenue),
+            (45, 20_000, 50_000)
+        );
+        assert_eq!(bands[1].time, 60);
+    }
+
+    #[test]
+    fn reports_the_line_of_a_malformed_row() {
+        match parse_bands("Onyx Eyes\t\t45 minutes\t20,000\t50,000\n\nSonderbund\t\t60\tlots\t1") {
+            Err(RosterError::Malformed { line, message }) => {
+                assert_eq!(line, 3);
+                assert_eq!(message, "invalid dollar amount \"lots\"");
+            }
+            other => panic!("expected a malformed row, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_a_roster_without_bands() {
+        assert!(matches!(parse_bands(""), Err(RosterError::Empty)));
+        assert!(matches!(parse_bands("\n  \n"), Err(RosterError::Empty)));
+    }
+}
@@ -0,0 +1,519 @@
+//! A line-oriented language for booking rules, one rule per line:
+//!
+//! ```text
+//! # comments run to the end of the line
+//! "Macy Dynamite" starts_after 21:00
+//! "Hummingbird Anthem" starts_before 21:00
+//! "Illiterate Monkeys" never_same_stage_as "Fractured Coccyx", "Macaulay & Co."
+//! "Onyx Eyes" requires "Chicken Fried Awesome"
+//! "Fractured Coccyx" not_in tent
+//! "Macaulay & Co." closes_stage
+//! "DJ Megara" not_adjacent_to "DJ Swedissh Cheff"
+//! "Infu$ion" back_to_back
+//! "Sloth Central Incorporated" same_stage
+//! "Caffeine Buzz" min_gap 120
+//! "Caffeine Buzz" different_days
+//! "Sonderbund" available_on Friday, Sunday
+//! "Macaulay & Co." closes_stage on Saturday
+//! ```
+//!
+//! Any rule may end with `on DAY` to apply only to that day of a multi-day festival.
+//! Each rule compiles to a [`ConstraintConfig`], the same as a `[[constraints]]` table.
+
+use crate::clock::ClockTime;
+use crate::config::ConfigError;
+use crate::constraints::{Constraint, ConstraintConfig};
+use crate::names::BandNames;
+use core::fmt;
+use std::path::Path;
+
+/// An error in a rules file, pointing at a 1-based line and column
+#[derive(Debug)]
+pub struct RuleError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// What a name in a rule refers to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NameKind {
+    Band,
+    Venue,
+    Day,
+}
+
+/// A rule from a rules file and the 1-based line and column where it starts
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub line: usize,
+    pub column: usize,
+    pub constraint: ConstraintConfig,
+    /// the column, kind and text of each name in the rule, in order
+    pub names: Vec<(usize, NameKind, String)>,
+}
+
+impl Rule {
+    /// build the rule's constraint, reporting an unknown name at its position in `path`
+    pub fn build(
+        &self,
+        path: &Path,
+        bands: &BandNames,
+        venues: &[String],
+        days: &[String],
+    ) -> Result<Box<dyn Constraint>, ConfigError> {
+        self.constraint.build(bands, venues, days).map_err(|err| {
+            let message = match err {
+                ConfigError::Invalid(message) => message,
+                err => err.to_string(),
+            };
+            let unknown = self.names.iter().find(|(_, kind, name)| match kind {
+                NameKind::Band => bands.id(name).is_none(),
+                NameKind::Venue => !venues.contains(name),
+                NameKind::Day => !days.contains(name),
+            });
+            ConfigError::Rules(
+                path.to_path_buf(),
+                RuleError {
+                    line: self.line,
+                    column: unknown.map_or(self.column, |(column, ..)| *column),
+                    message,
+                },
+            )
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// a double quoted string, without the quotes
+    Quoted(String),
+    /// an unquoted word such as a keyword, venue or clock time
+    Word(String),
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Quoted(text) => write!(f, "{:?}", text),
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+/// Parse every rule in a rules file
+pub fn parse_rules(source: &str) -> Result<Vec<Rule>, RuleError> {
+    let mut rules = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let tokens = tokenize(line).map_err(|(column, message)| RuleError {
+            line: index + 1,
+            column,
+            message,
+        })?;
+        let column = match tokens.first() {
+            Some((column, _)) => *column,
+            None => continue,
+        };
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end: line.chars().count() + 1,
+            names: Vec::new(),
+        };
+        let constraint = parser.rule().map_err(|(column, message)| RuleError {
+            line: index + 1,
+            column,
+            message,
+        })?;
+        rules.push(Rule {
+            line: index + 1,
+            column,
+            constraint,
+            names: parser.names,
+        });
+    }
+    Ok(rules)
+}
+
+/// split a line into tokens and their columns, dropping any comment
+fn tokenize(line: &str) -> Result<Vec<(usize, Token)>, (usize, String)> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().enumerate().peekable();
+    while let Some(&(index, c)) = chars.peek() {
+        let column = index + 1;
+        match c {
+            '#' => break,
+            ',' => {
+                chars.next();
+                tokens.push((column, Token::Comma));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => text.push(c),
+                        None => return Err((column, "unterminated band name".to_string())),
+                    }
+                }
+                tokens.push((column, Token::Quoted(text)));
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || c == ',' || c == '"' || c == '#' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push((column, Token::Word(word)));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// the column just past the end of the line, for errors about missing tokens
+    end: usize,
+    /// the names read so far, with their columns
+    names: Vec<(usize, NameKind, String)>,
+}
+
+impl Parser {
+    fn rule(&mut self) -> Result<ConstraintConfig, (usize, String)> {
+        let band = self.band()?;
+        let (column, keyword) = self.word("a rule such as starts_after")?;
+        let rule = match keyword.as_str() {
+            "starts_after" => ConstraintConfig::StartsAfter {
+                band,
+                time: self.time()?,
+            },
+            "starts_before" => ConstraintConfig::StartsBefore {
+                band,
+                time: self.time()?,
+            },
+            "not_in" => ConstraintConfig::NotIn {
+                band,
+                venue: self.venue()?,
+            },
+            "never_same_stage_as" => ConstraintConfig::NeverSameStageAs {
+                band,
+                others: self.band_list()?,
+            },
+            "requires" => ConstraintConfig::Requires {
+                band,
+                other: self.band()?,
+            },
+            "closes_stage" => ConstraintConfig::ClosesStage { band },
+            "not_adjacent_to" => ConstraintConfig::NotAdjacentTo {
+                band,
+                others: self.band_list()?,
+            },
+            "back_to_back" => ConstraintConfig::BackToBack { band },
+            "same_stage" => ConstraintConfig::SameStage { band },
+            "min_gap" => ConstraintConfig::MinGap {
+                band,
+                minutes: self.minutes()?,
+            },
+            "different_days" => ConstraintConfig::DifferentDays { band },
+            "available_on" => ConstraintConfig::AvailableOn {
+                band,
+                days: self.day_list()?,
+            },
+            _ => return Err((column, format!("unknown rule {}", keyword))),
+        };
+        let rule = match self.tokens.get(self.position) {
+            Some((_, Token::Word(word))) if word == "on" => {
+                self.position += 1;
+                ConstraintConfig::OnDay {
+                    day: self.day()?,
+                    rule: Box::new(rule),
+                }
+            }
+            _ => rule,
+        };
+        match self.tokens.get(self.position) {
+            Some((column, token)) => Err((*column, format!("unexpected {} after rule", token))),
+            None => Ok(rule),
+        }
+    }
+    fn next(&mut self) -> Option<(usize, &Token)> {
+        let (column, token) = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some((*column, token))
+    }
+    fn band(&mut self) -> Result<String, (usize, String)> {
+        let end = self.end;
+        let name = match self.next() {
+            Some((column, Token::Quoted(name))) => (column, name.clone()),
+            Some((column, token)) => {
+                return Err((
+                    column,
+                    format!("expected a quoted band name, found {}", token),
+                ))
+            }
+            None => return Err((end, "expected a quoted band name".to_string())),
+        };
+        Ok(self.name(name, NameKind::Band))
+    }
+    /// record a name and its column
+    fn name(&mut self, (column, name): (usize, String), kind: NameKind) -> String {
+        self.names.push((column, kind, name.clone()));
+        name
+    }
+    fn word(&mut self, expected: &str) -> Result<(usize, String), (usize, String)> {
+        let end = self.end;
+        match self.next() {
+            Some((column, Token::Word(word))) => Ok((column, word.clone())),
+            Some((column, token)) => {
+                Err((column, format!("expected {}, found {}", expected, token)))
+            }
+            None => Err((end, format!("expected {}", expected))),
+        }
+    }
+    fn time(&mut self) -> Result<String, (usize, String)> {
+        let (column, time) = self.word("a clock time such as 21:00")?;
+        time.parse::<ClockTime>()
+            .map_err(|err| (column, err.to_string()))?;
+        Ok(time)
+    }
+    /// a number of minutes, such as 90
+    fn minutes(&mut self) -> Result<u32, (usize, String)> {
+        let (column, minutes) = self.word("a number of minutes such as 90")?;
+        minutes
+            .parse()
+            .map_err(|_| (column, format!("invalid number of minutes {}", minutes)))
+    }
+    /// a venue name, quoted or not
+    fn venue(&mut self) -> Result<String, (usize, String)> {
+        let end = self.end;
+        let name = match self.next() {
+            Some((column, Token::Quoted(name))) | Some((column, Token::Word(name))) => {
+                (column, name.clone())
+            }
+            Some((column, token)) => {
+                return Err((column, format!("expected a venue, found {}", token)))
+            }
+            None => return Err((end, "expected a venue".to_string())),
+        };
+        Ok(self.name(name, NameKind::Venue))
+    }
+    /// a day name, quoted or not
+    fn day(&mut self) -> Result<String, (usize, String)> {
+        let end = self.end;
+        let name = match self.next() {
+            Some((column, Token::Quoted(name))) | Some((column, Token::Word(name))) => {
+                (column, name.clone())
+            }
+            Some((column, token)) => {
+                return Err((column, format!("expected a day, found {}", token)))
+            }
+            None => return Err((end, "expected a day".to_string())),
+        };
+        Ok(self.name(name, NameKind::Day))
+    }
+    /// one or more comma separated days
+    fn day_list(&mut self) -> Result<Vec<String>, (usize, String)> {
+        let mut days = vec![self.day()?];
+        while let Some((_, Token::Comma)) = self.tokens.get(self.position) {
+            self.position += 1;
+            days.push(self.day()?);
+        }
+        Ok(days)
+    }
+    /// one or more comma separated band names
+    fn band_list(&mut self) -> Result<Vec<String>, (usize, String)> {
+        let mut bands = vec![self.band()?];
+        while let Some((_, Token::Comma)) = self.tokens.get(self.position) {
+            self.position += 1;
+            bands.push(self.band()?);
+        }
+        Ok(bands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the constraint of a one-line rules file
+    fn rule(source: &str) -> ConstraintConfig {
+        let mut rules = parse_rules(source).unwrap();
+        assert_eq!(rules.len(), 1);
+        rules.remove(0).constraint
+    }
+
+    /// the line, column and message of a rules file's error
+    fn error(source: &str) -> (usize, usize, String) {
+        let err = parse_rules(source).unwrap_err();
+        (err.line, err.column, err.message)
+    }
+
+    fn band() -> String {
+        "Macy Dynamite".to_string()
+    }
+
+    fn others() -> Vec<String> {
+        vec!["Onyx Eyes".to_string(), "Macaulay & Co.".to_string()]
+    }
+
+    #[test]
+    fn parses_each_rule() {
+        let time = "21:00".to_string();
+        let cases = vec![
+            (
+                r#""Macy Dynamite" starts_after 21:00"#,
+                ConstraintConfig::StartsAfter {
+                    band: band(),
+                    time: time.clone(),
+                },
+            ),
+            (
+                r#""Macy Dynamite" starts_before 21:00"#,
+                ConstraintConfig::StartsBefore { band: band(), time },
+            ),
+            (
+                r#""Macy Dynamite" not_in "main stage""#,
+                ConstraintConfig::NotIn {
+                    band: band(),
+                    venue: "main stage".to_string(),
+                },
+            ),
+            (
+                r#""Macy Dynamite" never_same_stage_as "Onyx Eyes", "Macaulay & Co.""#,
+                ConstraintConfig::NeverSameStageAs {
+                    band: band(),
+                    others: others(),
+                },
+            ),
+            (
+                r#""Macy Dynamite" requires "Onyx Eyes""#,
+                ConstraintConfig::Requires {
+                    band: band(),
+                    other: "Onyx Eyes".to_string(),
+                },
+            ),
+            (
+                r#""Macy Dynamite" closes_stage"#,
+                ConstraintConfig::ClosesStage { band: band() },
+            ),
+            (
+                r#""Macy Dynamite" not_adjacent_to "Onyx Eyes","Macaulay & Co.""#,
+                ConstraintConfig::NotAdjacentTo {
+                    band: band(),
+                    others: others(),
+                },
+            ),
+            (
+                r#""Macy Dynamite" back_to_back"#,
+                ConstraintConfig::BackToBack { band: band() },
+            ),
+            (
+                r#""Macy Dynamite" same_stage"#,
+                ConstraintConfig::SameStage { band: band() },
+            ),
+            (
+                r#""Macy Dynamite" min_gap 120"#,
+                ConstraintConfig::MinGap {
+                    band: band(),
+                    minutes: 120,
+                },
+            ),
+            (
+                r#""Macy Dynamite" different_days"#,
+                ConstraintConfig::DifferentDays { band: band() },
+            ),
+            (
+                r#""Macy Dynamite" available_on Friday , Sunday"#,
+                ConstraintConfig::AvailableOn {
+                    band: band(),
+                    days: vec!["Friday".to_string(), "Sunday".to_string()],
+                },
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(rule(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn limits_a_rule_to_a_day() {
+        assert_eq!(
+            rule(r#""Macy Dynamite" not_in tent on "Saturday""#),
+            ConstraintConfig::OnDay {
+                day: "Saturday".to_string(),
+                rule: Box::new(ConstraintConfig::NotIn {
+                    band: band(),
+                    venue: "tent".to_string(),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let rules = parse_rules(
+            "# the headliner\n\n   \n  \"Macy Dynamite\" closes_stage # last\n\"Onyx Eyes\" same_stage",
+        )
+        .unwrap();
+        let positions: Vec<_> = rules.iter().map(|rule| (rule.line, rule.column)).collect();
+        assert_eq!(positions, vec![(4, 3), (5, 1)]);
+        assert_eq!(
+            rules[0].constraint,
+            ConstraintConfig::ClosesStage { band: band() }
+        );
+        assert_eq!(parse_rules("# nothing but a comment").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn points_at_the_error() {
+        assert_eq!(
+            error("\n  \"Macy Dynamite closes_stage"),
+            (2, 3, "unterminated band name".to_string())
+        );
+        assert_eq!(
+            error("\"Macy Dynamite\" closes_stage\n\"Onyx Eyes\" opens_stage"),
+            (2, 13, "unknown rule opens_stage".to_string())
+        );
+        assert_eq!(
+            error("\"Macy Dynamite\" starts_after 25:00"),
+            (1, 30, "invalid clock time \"25:00\"".to_string())
+        );
+        assert_eq!(
+            error("\"Macy Dynamite\" closes_stage tent"),
+            (1, 30, "unexpected tent after rule".to_string())
+        );
+        assert_eq!(
+            error("\"Macy Dynamite\" requires"),
+            (1, 25, "expected a quoted band name".to_string())
+        );
+        assert_eq!(
+            error("\"Macy Dynamite\" never_same_stage_as \"Onyx Eyes\","),
+            (1, 49, "expected a quoted band name".to_string())
+        );
+        assert_eq!(
+            error("closes_stage \"Macy Dynamite\""),
+            (
+                1,
+                1,
+                "expected a quoted band name, found closes_stage".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::cooling::ScheduleKind;
+use crate::violation::Violation;
+use crate::{Econochella, Location, Money};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Simulated annealing over band moves
+///
+/// Starting from the initial festival as both the running and the best state, for each iteration:
+///     Create a candidate by moving a random band of the running state to a random location
+///     (choosing the same location amounts to a deletion and a random reinsertion)
+///     If the candidate is valid
+///         delta = value of the candidate - value of the running state
+///         Accept the candidate as the running state by the Metropolis criterion:
+///             always if delta >= 0, otherwise with probability e ^ (delta / temperature)
+///         If the running state is better than the best state, update the best state
+///     Step the cooling schedule
+pub struct SimulatedAnnealing {
+    pub iterations: usize,
+    pub schedule: ScheduleKind,
+    pub initial_temperature: f64,
+}
+
+/// The outcome of a search
+pub struct SearchResult {
+    /// the best valid festival seen
+    pub best: Econochella,
+    /// the running state's value after each iteration
+    pub scores: Vec<Money>,
+    /// how many candidates each check rejected, by violation label
+    pub rejections: HashMap<String, u32>,
+}
+
+impl SimulatedAnnealing {
+    pub fn solve<R: Rng + ?Sized>(&self, initial: &Econochella, rng: &mut R) -> SearchResult {
+        let mut schedule = self
+            .schedule
+            .build(self.initial_temperature, self.iterations);
+        let mut best = initial.clone();
+        let mut running = initial.clone();
+        let mut running_value = running.value();
+        let mut best_value = best.value();
+        let mut scores = Vec::with_capacity(self.iterations);
+        let mut rejections = HashMap::new();
+
+        for _ in 0..self.iterations {
+            let candidate = neighbour(&running, rng);
+            let violations = candidate.validate();
+            let mut accepted = false;
+            if violations.is_empty() {
+                let candidate_value = candidate.value();
+                let delta = (candidate_value - running_value) as f64;
+                if metropolis_accept(delta, schedule.temperature(), rng) {
+                    running = candidate;
+                    running_value = candidate_value;
+                    accepted = true;
+                }
+                if running_value > best_value {
+                    best = running.clone();
+                    best_value = running_value;
+                }
+            } else {
+                count_rejection(&mut rejections, &violations);
+            }
+            schedule.step(accepted);
+            scores.push(running_value);
+        }
+        SearchResult {
+            best,
+            scores,
+            rejections,
+        }
+    }
+}
+
+/// count a rejected candidate once under each check it fails
+pub(crate) fn count_rejection(rejections: &mut HashMap<String, u32>, violations: &[Violation]) {
+    let mut labels: Vec<String> = violations.iter().map(Violation::label).collect();
+    labels.sort();
+    labels.dedup();
+    for label in labels {
+        *rejections.entry(label).or_insert(0) += 1;
+    }
+}
+
+/// a copy of the festival with one random band moved to a random location
+pub fn neighbour<R: Rng + ?Sized>(festival: &Econochella, rng: &mut R) -> Econochella {
+    random_move(festival, rng).0
+}
+
+/// a copy of the festival with one random band moved to a random location,
+/// with the band's index and the location it left
+pub fn random_move<R: Rng + ?Sized>(
+    festival: &Econochella,
+    rng: &mut R,
+) -> (Econochella, usize, Location) {
+    let mut candidate = festival.clone();
+    let (band, current_location) = candidate.choose_band(rng);
+    let new_location = current_location.choose_location(candidate.venues.len(), rng);
+    candidate.move_band(band, current_location.clone(), new_location, rng);
+    (candidate, band, current_location)
+}
+
+/// The Metropolis criterion for maximising value: accept any move that does not lose value,
+/// and a move losing value with probability e ^ (delta / temperature)
+pub fn metropolis_accept<R: Rng + ?Sized>(delta: f64, temperature: f64, rng: &mut R) -> bool {
+    if delta >= 0.0 {
+        return true;
+    }
+    if temperature <= 0.0 {
+        return false;
+    }
+    rng.gen::<f64>() < (delta / temperature).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::toy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// the fraction of `trials` worsening moves by `delta` accepted at `temperature`
+    fn acceptance_rate(delta: f64, temperature: f64, trials: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(7);
+        let accepted = (0..trials)
+            .filter(|_| metropolis_accept(delta, temperature, &mut rng))
+            .count();
+        accepted as f64 / trials as f64
+    }
+
+    #[test]
+    fn accepts_improving_and_equal_moves() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(metropolis_accept(10.0, 1.0, &mut rng));
+        assert!(metropolis_accept(0.0, 1.0, &mut rng));
+        assert!(metropolis_accept(0.0, 0.0, &mut rng));
+    }
+
+    #[test]
+    fn rejects_worsening_moves_when_frozen() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(!metropolis_accept(-1.0, 0.0, &mut rng));
+    }
+
+    #[test]
+    fn accepts_worsening_moves_with_metropolis_probability() {
+        let mut festival = toy();
+        let mut rng = StdRng::seed_from_u64(1);
+        festival.move_band(0, Location::Unused, Location::Venue(0), &mut rng);
+        let mut removed = festival.clone();
+        removed.move_band(0, Location::Venue(0), Location::Unused, &mut rng);
+        let delta = (removed.value() - festival.value()) as f64;
+        assert_eq!(delta, -140.0);
+
+        for expected in [0.5, 0.25, 0.1] {
+            let temperature = delta / f64::ln(expected);
+            let rate = acceptance_rate(delta, temperature, 20_000);
+            assert!(
+                (rate - expected).abs() < 0.015,
+                "accepted {} of moves, expected {}",
+                rate,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_worse_neighbours_only_when_hot() {
+        // the number of iterations after which the running state was worth less than before
+        let losses = |initial_temperature| {
+            let annealing = SimulatedAnnealing {
+                iterations: 500,
+                schedule: ScheduleKind::Geometric,
+                initial_temperature,
+            };
+            let scores = annealing
+                .solve(&toy(), &mut StdRng::seed_from_u64(5))
+                .scores;
+            scores.windows(2).filter(|pair| pair[1] < pair[0]).count()
+        };
+        assert!(losses(1e9) > 10);
+        assert_eq!(losses(1e-9), 0);
+    }
+
+    #[test]
+    fn finds_the_toy_optimum() {
+        let annealing = SimulatedAnnealing {
+            iterations: 2_000,
+            schedule: ScheduleKind::Geometric,
+            initial_temperature: 100.0,
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let result = annealing.solve(&toy(), &mut rng);
+        assert_eq!(result.best.value(), 220);
+        assert!(result.best.validate().is_empty());
+        assert_eq!(result.scores.len(), 2_000);
+    }
+
+    #[test]
+    fn replays_a_seed() {
+        let annealing = SimulatedAnnealing {
+            iterations: 500,
+            schedule: ScheduleKind::Geometric,
+            initial_temperature: 1e3,
+        };
+        let run = || annealing.solve(&toy(), &mut StdRng::seed_from_u64(11));
+        let (first, second) = (run(), run());
+        assert_eq!(first.scores, second.scores);
+        assert_eq!(first.rejections, second.rejections);
+        let locations = |result: &SearchResult| -> Vec<Location> {
+            let knapsack = result.best.knapsack();
+            knapsack
+                .iter()
+                .map(|(_, location)| location.clone())
+                .collect()
+        };
+        assert_eq!(locations(&first), locations(&second));
+        assert_eq!(first.best.value(), second.best.value());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::solver::{count_rejection, random_move, SearchResult};
+use crate::{Econochella, Location, Money};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Tabu search over band moves
+///
+/// Starting from the initial festival as both the running and the best state, for each iteration:
+///     Sample `candidates` random moves of the running state, the same moves annealing makes
+///     Discard the invalid candidates, and the tabu ones: those returning a band to a location
+///     it left within the last `tenure` iterations, unless they beat the best state (aspiration)
+///     Move to the most valuable remaining candidate, even if it is worse than the running state,
+///     and make returning the moved band to the location it left tabu
+///     If the running state is better than the best state, update the best state
+pub struct TabuSearch {
+    pub iterations: usize,
+    /// moves sampled per iteration
+    pub candidates: usize,
+    /// iterations for which a band may not return to a location it left
+    pub tenure: usize,
+}
+
+impl TabuSearch {
+    pub fn solve<R: Rng + ?Sized>(&self, initial: &Econochella, rng: &mut R) -> SearchResult {
+        let mut best = initial.clone();
+        let mut running = initial.clone();
+        let mut running_value = running.value();
+        let mut best_value = best.value();
+        let mut scores = Vec::with_capacity(self.iterations);
+        let mut rejections = HashMap::new();
+        // (band index, location it left) -> the first iteration it may return
+        let mut tabu: HashMap<(usize, Location), usize> = HashMap::new();
+
+        for iteration in 0..self.iterations {
+            let mut chosen: Option<(Econochella, Money, usize, Location)> = None;
+            for _ in 0..self.candidates {
+                let (candidate, band, left) = random_move(&running, rng);
+                let violations = candidate.validate();
+                if !violations.is_empty() {
+                    count_rejection(&mut rejections, &violations);
+                    continue;
+                }
+                let value = candidate.value();
+                let destination = candidate.knapsack[band].1.clone();
+                let is_tabu = destination != left
+                    && tabu
+                        .get(&(band, destination))
+                        .is_some_and(|&until| iteration < until);
+                if is_tabu && value <= best_value {
+                    continue;
+                }
+                if chosen.as_ref().is_none_or(|chosen| value > chosen.1) {
+                    chosen = Some((candidate, value, band, left));
+                }
+            }
+            if let Some((candidate, value, band, left)) = chosen {
+                if candidate.knapsack[band].1 != left {
+                    tabu.insert((band, left), iteration + 1 + self.tenure);
+                }
+                running = candidate;
+                running_value = value;
+                if running_value > best_value {
+                    best = running.clone();
+                    best_value = running_value;
+                }
+            }
+            scores.push(running_value);
+        }
+        SearchResult {
+            best,
+            scores,
+            rejections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::toy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn finds_the_toy_optimum() {
+        let tabu = TabuSearch {
+            iterations: 200,
+            candidates: 10,
+            tenure: 3,
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let result = tabu.solve(&toy(), &mut rng);
+        assert_eq!(result.best.value(), 220);
+        assert!(result.best.validate().is_empty());
+        assert_eq!(result.scores.len(), 200);
+    }
+}
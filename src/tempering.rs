@@ -0,0 +1,206 @@
+use crate::solver::{count_rejection, metropolis_accept, neighbour, SearchResult};
+use crate::{Econochella, Money};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::thread;
+
+/// Parallel tempering (replica exchange) over band moves
+///
+/// Run `chains` copies of the initial festival at fixed temperatures spaced geometrically
+/// from `min_temperature` up to `max_temperature`, each on its own thread with its own seeded
+/// random number generator. Every `swap_interval` iterations:
+///     Wait for every chain, then for each pair of neighbouring temperatures,
+///     swap the chains' states with probability e ^ ((hot value - cold value) * (1 / cold - 1 / hot)),
+///     so a hot chain that finds something better hands it down to be refined
+/// The best valid festival any chain saw is the result
+pub struct ParallelTempering {
+    /// iterations of each chain
+    pub iterations: usize,
+    pub chains: usize,
+    /// iterations between swap attempts
+    pub swap_interval: usize,
+    pub min_temperature: f64,
+    pub max_temperature: f64,
+}
+
+/// The outcome of parallel tempering
+pub struct Exchange {
+    /// the best lineup, the coldest chain's value after each iteration,
+    /// and how many candidates each check rejected across all chains
+    pub result: SearchResult,
+    /// each chain's temperature, coldest first
+    pub temperatures: Vec<f64>,
+    /// the fraction of attempted swaps accepted between each pair of neighbouring temperatures
+    pub swap_rates: Vec<f64>,
+}
+
+/// One replica: a running state at a fixed temperature
+struct Chain {
+    temperature: f64,
+    running: Econochella,
+    running_value: Money,
+    best: Econochella,
+    best_value: Money,
+    scores: Vec<Money>,
+    rejections: HashMap<String, u32>,
+    rng: StdRng,
+}
+
+impl Chain {
+    /// annealing at a constant temperature
+    fn run(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            let candidate = neighbour(&self.running, &mut self.rng);
+            let violations = candidate.validate();
+            if violations.is_empty() {
+                let candidate_value = candidate.value();
+                let delta = (candidate_value - self.running_value) as f64;
+                if metropolis_accept(delta, self.temperature, &mut self.rng) {
+                    self.running = candidate;
+                    self.running_value = candidate_value;
+                }
+                if self.running_value > self.best_value {
+                    self.best = self.running.clone();
+                    self.best_value = self.running_value;
+                }
+            } else {
+                count_rejection(&mut self.rejections, &violations);
+            }
+            self.scores.push(self.running_value);
+        }
+    }
+}
+
+impl ParallelTempering {
+    /// the temperature of each chain, coldest first
+    pub fn temperatures(&self) -> Vec<f64> {
+        let chains = self.chains.max(1);
+        if chains == 1 {
+            return vec![self.min_temperature];
+        }
+        let ratio = (self.max_temperature / self.min_temperature).powf(1.0 / (chains - 1) as f64);
+        (0..chains)
+            .map(|chain| self.min_temperature * ratio.powi(chain as i32))
+            .collect()
+    }
+
+    pub fn solve<R: Rng + ?Sized>(&self, initial: &Econochella, rng: &mut R) -> Exchange {
+        let temperatures = self.temperatures();
+        let initial_value = initial.value();
+        // seed every chain from the caller's generator, so that a seed replays the whole run
+        let mut chains: Vec<Chain> = temperatures
+            .iter()
+            .map(|&temperature| Chain {
+                temperature,
+                running: initial.clone(),
+                running_value: initial_value,
+                best: initial.clone(),
+                best_value: initial_value,
+                scores: Vec::with_capacity(self.iterations),
+                rejections: HashMap::new(),
+                rng: StdRng::seed_from_u64(rng.gen()),
+            })
+            .collect();
+        let mut attempts = vec![0u32; chains.len().saturating_sub(1)];
+        let mut swaps = vec![0u32; attempts.len()];
+
+        let interval = self.swap_interval.max(1);
+        let mut done = 0;
+        while done < self.iterations {
+            let steps = interval.min(self.iterations - done);
+            thread::scope(|scope| {
+                for chain in &mut chains {
+                    scope.spawn(move || chain.run(steps));
+                }
+            });
+            done += steps;
+            if done == self.iterations {
+                break;
+            }
+            for cold in 0..attempts.len() {
+                let hot = cold + 1;
+                let delta = (chains[hot].running_value - chains[cold].running_value) as f64
+                    * (1.0 / chains[cold].temperature - 1.0 / chains[hot].temperature);
+                attempts[cold] += 1;
+                if delta >= 0.0 || rng.gen::<f64>() < delta.exp() {
+                    swaps[cold] += 1;
+                    let (colder, hotter) = chains.split_at_mut(hot);
+                    let (cold, hot) = (&mut colder[cold], &mut hotter[0]);
+                    std::mem::swap(&mut cold.running, &mut hot.running);
+                    std::mem::swap(&mut cold.running_value, &mut hot.running_value);
+                }
+            }
+        }
+
+        let mut rejections = HashMap::new();
+        for chain in &chains {
+            for (label, count) in &chain.rejections {
+                *rejections.entry(label.clone()).or_insert(0) += count;
+            }
+        }
+        let swap_rates = swaps
+            .iter()
+            .zip(&attempts)
+            .map(|(&swaps, &attempts)| swaps as f64 / attempts.max(1) as f64)
+            .collect();
+        let mut chains = chains.into_iter();
+        let coldest = chains.next().expect("there is always a chain");
+        let scores = coldest.scores;
+        let mut best = (coldest.best, coldest.best_value);
+        for chain in chains {
+            if chain.best_value > best.1 {
+                best = (chain.best, chain.best_value);
+            }
+        }
+        Exchange {
+            result: SearchResult {
+                best: best.0,
+                scores,
+                rejections,
+            },
+            temperatures,
+            swap_rates,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::toy;
+
+    fn tempering() -> ParallelTempering {
+        ParallelTempering {
+            iterations: 500,
+            chains: 3,
+            swap_interval: 50,
+            min_temperature: 1.0,
+            max_temperature: 100.0,
+        }
+    }
+
+    #[test]
+    fn spaces_temperatures_geometrically() {
+        let temperatures = tempering().temperatures();
+        assert_eq!(temperatures.len(), 3);
+        assert!((temperatures[1] - 10.0).abs() < 1e-9);
+        assert!((temperatures[2] - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn finds_the_toy_optimum_and_replays_under_a_seed() {
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            tempering().solve(&toy(), &mut rng)
+        };
+        let exchange = run(3);
+        assert_eq!(exchange.result.best.value(), 220);
+        assert!(exchange.result.best.validate().is_empty());
+        assert_eq!(exchange.result.scores.len(), 500);
+        assert_eq!(exchange.swap_rates.len(), 2);
+        let replay = run(3);
+        assert_eq!(replay.result.scores, exchange.result.scores);
+        assert_eq!(replay.swap_rates, exchange.swap_rates);
+    }
+}
@@ -0,0 +1,25 @@
+//! Fixtures shared by the solvers' tests
+
+use crate::clock::ClockTime;
+use crate::{Band, Econochella, Money, Venue};
+
+/// an hour-long set
+pub(crate) fn band(name: &str, revenue: Money, cost: Money) -> Band {
+    Band {
+        name: name.into(),
+        time: 60,
+        revenue,
+        cost,
+        ..Band::default()
+    }
+}
+
+/// one two-hour stage with no breaks and a budget of 100,
+/// where the best lineup is a and c for a value of 220
+pub(crate) fn toy() -> Econochella {
+    Econochella::new(
+        100,
+        vec![Venue::new("stage".to_string(), ClockTime::NOON, 0, 120)],
+        vec![band("a", 200, 60), band("b", 150, 50), band("c", 120, 40)],
+    )
+}
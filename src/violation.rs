@@ -0,0 +1,216 @@
+use crate::clock::ClockTime;
+use crate::names::BandRef;
+use crate::Money;
+use core::fmt;
+use core::slice;
+
+/// A reason a festival's schedules are not valid
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// the booked bands cost more than the budget
+    OverBudget { cost: Money, budget: Money },
+    /// a venue's schedule runs past its curfew
+    OverTime {
+        venue: String,
+        used: u32,
+        available: u32,
+        curfew: ClockTime,
+    },
+    /// a band's set is booked without the set before it
+    SetSkipped { band: BandRef, set: usize },
+    /// a band's set overlaps the set before it, on any stage
+    SetsOverlap { band: BandRef, set: usize },
+    /// a band's set starts before the set before it
+    SetOutOfOrder { band: BandRef, set: usize },
+    /// a booking rule is broken
+    Constraint {
+        /// description of the rule
+        rule: String,
+        /// the bands involved, the rule's own band first
+        bands: Vec<BandRef>,
+        /// the venue where the rule is broken, if it is about one venue
+        venue: Option<String>,
+    },
+}
+
+impl Violation {
+    /// the venue whose schedule alone causes the violation, if any
+    pub fn venue(&self) -> Option<&str> {
+        match self {
+            Violation::OverBudget { .. }
+            | Violation::SetSkipped { .. }
+            | Violation::SetsOverlap { .. }
+            | Violation::SetOutOfOrder { .. } => None,
+            Violation::OverTime { venue, .. } => Some(venue),
+            Violation::Constraint { venue, .. } => venue.as_deref(),
+        }
+    }
+    /// the bands involved, none for the budget or a venue's hours
+    pub fn bands(&self) -> &[BandRef] {
+        match self {
+            Violation::OverBudget { .. } | Violation::OverTime { .. } => &[],
+            Violation::SetSkipped { band, .. }
+            | Violation::SetsOverlap { band, .. }
+            | Violation::SetOutOfOrder { band, .. } => slice::from_ref(band),
+            Violation::Constraint { bands, .. } => bands,
+        }
+    }
+    /// a label grouping violations of the same check, for statistics
+    pub fn label(&self) -> String {
+        match self {
+            Violation::OverBudget { .. } => "over budget".to_string(),
+            Violation::OverTime { venue, .. } => format!("over time in the {}", venue),
+            Violation::SetSkipped { band, .. } => format!("{} sets skipped", band),
+            Violation::SetsOverlap { band, .. } => format!("{} sets overlapping", band),
+            Violation::SetOutOfOrder { band, .. } => format!("{} sets out of order", band),
+            Violation::Constraint { rule, .. } => rule.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::OverBudget { cost, budget } => write!(
+                f,
+                "over budget by {}: the bands cost {} of {}",
+                cost - budget,
+                cost,
+                budget
+            ),
+            Violation::OverTime {
+                venue,
+                used,
+                available,
+                curfew,
+            } => write!(
+                f,
+                "the {} runs {} minutes past its {} curfew: {} of {} minutes used",
+                venue,
+                used - available,
+                curfew,
+                used,
+                available
+            ),
+            Violation::SetSkipped { band, set } => write!(
+                f,
+                "{} is booked for set {} without set {}",
+                band,
+                set + 1,
+                set
+            ),
+            Violation::SetsOverlap { band, set } => write!(
+                f,
+                "{} plays set {} while set {} is still on",
+                band,
+                set + 1,
+                set
+            ),
+            Violation::SetOutOfOrder { band, set } => {
+                write!(f, "{} plays set {} before set {}", band, set + 1, set)
+            }
+            Violation::Constraint { rule, bands, venue } => {
+                let names: Vec<&str> = bands.iter().map(|band| &*band.name).collect();
+                write!(f, "broken rule \"{}\" by {}", rule, names.join(", "))?;
+                if let Some(venue) = venue {
+                    write!(f, " in the {}", venue)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::names::BandNames;
+
+    /// one violation of each kind, by band a in the tent where there is one
+    fn violations() -> Vec<Violation> {
+        let mut names = BandNames::default();
+        let a = names.intern("a");
+        let band = BandRef {
+            id: a,
+            name: names.name(a).clone(),
+        };
+        vec![
+            Violation::OverBudget {
+                cost: 120,
+                budget: 100,
+            },
+            Violation::OverTime {
+                venue: "tent on Friday".to_string(),
+                used: 310,
+                available: 300,
+                curfew: ClockTime::from_minutes_since_noon(600),
+            },
+            Violation::SetSkipped {
+                band: band.clone(),
+                set: 1,
+            },
+            Violation::SetsOverlap {
+                band: band.clone(),
+                set: 1,
+            },
+            Violation::SetOutOfOrder {
+                band: band.clone(),
+                set: 1,
+            },
+            Violation::Constraint {
+                rule: "a closes the stage".to_string(),
+                bands: vec![band.clone()],
+                venue: Some("tent on Friday".to_string()),
+            },
+            Violation::Constraint {
+                rule: "a requires b".to_string(),
+                bands: vec![band],
+                venue: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn names_the_venue_only_of_schedule_violations() {
+        let violations = violations();
+        let venues: Vec<Option<&str>> = violations.iter().map(Violation::venue).collect();
+        assert_eq!(
+            venues,
+            vec![
+                None,
+                Some("tent on Friday"),
+                None,
+                None,
+                None,
+                Some("tent on Friday"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_each_check() {
+        let labels: Vec<String> = violations().iter().map(Violation::label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "over budget",
+                "over time in the tent on Friday",
+                "a sets skipped",
+                "a sets overlapping",
+                "a sets out of order",
+                "a closes the stage",
+                "a requires b",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_the_bands_involved() {
+        let bands: Vec<usize> = violations()
+            .iter()
+            .map(|violation| violation.bands().len())
+            .collect();
+        assert_eq!(bands, vec![0, 0, 1, 1, 1, 1, 1]);
+    }
+}
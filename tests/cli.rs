@@ -0,0 +1,142 @@
+//! The command line's exit codes: 0 success, 2 usage error, 3 invalid input file,
+//! 4 infeasible lineup
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// a fresh directory holding a two-hour tent, a roster of three hour-long bands a, b and c,
+/// and the named lineup files
+fn festival(test: &str, lineups: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("econochella-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("festival.toml"),
+        r#"
+        budget = 100_000
+        roster = "bands.txt"
+
+        [[venues]]
+        name = "tent"
+        opens = "17:00"
+        closes = "19:00"
+        break = 0
+        "#,
+    )
+    .unwrap();
+    let roster: String = ["a", "b", "c"]
+        .iter()
+        .map(|name| format!("{}\t\t60 minutes\t10,000\t30,000\n", name))
+        .collect();
+    fs::write(dir.join("bands.txt"), roster).unwrap();
+    for (name, contents) in lineups {
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    dir
+}
+
+/// run the command line with the arguments, naming files in `dir` by their file names
+fn run(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_econochella"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn code(output: &Output) -> i32 {
+    output.status.code().unwrap()
+}
+
+#[test]
+fn accepts_a_valid_lineup() {
+    let dir = festival(
+        "valid",
+        &[(
+            "lineup.toml",
+            "[[venues]]\nname = \"tent\"\nbands = [\"a\", \"b\"]\n",
+        )],
+    );
+    let output = run(&dir, &["validate", "festival.toml", "lineup.toml"]);
+    assert_eq!(code(&output), 0);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Valid"));
+}
+
+#[test]
+fn rejects_bad_usage() {
+    let dir = festival("usage", &[]);
+    assert_eq!(code(&run(&dir, &[])), 2);
+    assert_eq!(code(&run(&dir, &["frobnicate"])), 2);
+    assert_eq!(code(&run(&dir, &["validate", "festival.toml"])), 2);
+    assert_eq!(
+        code(&run(&dir, &["solve", "festival.toml", "--chains", "1"])),
+        2
+    );
+}
+
+#[test]
+fn rejects_invalid_input_files() {
+    let dir = festival(
+        "invalid",
+        &[(
+            "lineup.toml",
+            "[[venues]]\nname = \"tent\"\nbands = [\"d\"]\n",
+        )],
+    );
+    assert_eq!(
+        code(&run(&dir, &["validate", "festival.toml", "lineup.toml"])),
+        3
+    );
+    assert_eq!(
+        code(&run(&dir, &["validate", "missing.toml", "lineup.toml"])),
+        3
+    );
+    assert_eq!(
+        code(&run(&dir, &["validate", "festival.toml", "missing.toml"])),
+        3
+    );
+}
+
+#[test]
+fn reports_an_infeasible_lineup() {
+    let dir = festival(
+        "infeasible",
+        &[(
+            "lineup.toml",
+            "[[venues]]\nname = \"tent\"\nbands = [\"a\", \"b\", \"c\"]\n",
+        )],
+    );
+    let output = run(&dir, &["validate", "festival.toml", "lineup.toml"]);
+    assert_eq!(code(&output), 4);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Violations:"));
+    assert_eq!(
+        code(&run(&dir, &["explain", "festival.toml", "lineup.toml"])),
+        4
+    );
+}
+
+#[test]
+fn certifies_a_saved_lineup() {
+    let dir = festival("certify", &[]);
+    let output = run(
+        &dir,
+        &[
+            "solve",
+            "festival.toml",
+            "--seed",
+            "1",
+            "--format",
+            "lineup",
+            "--certify",
+        ],
+    );
+    assert_eq!(code(&output), 0);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("# Exact optimum: 40000"), "{}", stdout);
+    // the report is in comments, so the output is still a lineup file
+    fs::write(dir.join("lineup.toml"), stdout.as_bytes()).unwrap();
+    assert_eq!(
+        code(&run(&dir, &["validate", "festival.toml", "lineup.toml"])),
+        0
+    );
+}